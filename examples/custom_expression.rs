@@ -64,6 +64,7 @@ enum MyOperandErr<T> {
 // then the Evaluator needs the same TryFromRef signature.
 
 // A clear error struct/enum is really important for the evaluation part
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 enum MyEvalErr<T> {
     CannotAddOperands(T, T),
@@ -89,8 +90,9 @@ impl Evaluate<MyOperand> for MyEvaluator<MyOperand> {
         }
     }
 
+    #[allow(clippy::unit_arg)]
     fn evaluate(self, stack: &mut Stack<MyOperand>) -> Result<(), Self::Err> {
-        let (a, b) = pop_two_operands(stack).ok_or(MyEvalErr::NotEnoughOperands)?;
+        let (a, b) = pop_two_operands(stack).map_err(|_| MyEvalErr::NotEnoughOperands)?;
         match self {
             MyEvaluator::Add => {
                 match (a, b) {
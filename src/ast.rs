@@ -0,0 +1,133 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use stack::Stack;
+use evaluate::Evaluate;
+use expression::{Arithm, Expression, ExpressionBuilder, OperandErr};
+
+/// A node of the tree explicitly represented by an `RPN` [`Expression`].
+///
+/// [`Expression`]: ../expression/struct.Expression.html
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstNode<T, V, E> {
+    /// A literal operand.
+    Leaf(T),
+    /// A variable to be retrieved at evaluation time.
+    Variable(V),
+    /// An evaluator needing one operand.
+    UnaryOp(E, Box<AstNode<T, V, E>>),
+    /// An evaluator needing two operands.
+    BinaryOp(E, Box<AstNode<T, V, E>>, Box<AstNode<T, V, E>>),
+    /// An evaluator needing any other number of operands.
+    NaryOp(E, Vec<Box<AstNode<T, V, E>>>),
+}
+
+impl<T, V, E: Evaluate<T>> AstNode<T, V, E> {
+    fn append_to(self, builder: ExpressionBuilder<T, V, E>) -> ExpressionBuilder<T, V, E> {
+        match self {
+            AstNode::Leaf(operand) => builder.push_operand(operand),
+            AstNode::Variable(variable) => builder.push_variable(variable),
+            AstNode::UnaryOp(evaluator, a) => a.append_to(builder).push_evaluator(evaluator),
+            AstNode::BinaryOp(evaluator, a, b) => {
+                b.append_to(a.append_to(builder)).push_evaluator(evaluator)
+            }
+            AstNode::NaryOp(evaluator, args) => {
+                args.into_iter()
+                    .fold(builder, |builder, arg| arg.append_to(builder))
+                    .push_evaluator(evaluator)
+            }
+        }
+    }
+}
+
+impl<T: Clone, V: Clone, E: Evaluate<T> + Clone> Expression<T, V, E> {
+    /// Converts the expression to an explicit tree,
+    /// returns `None` if the expression contains an evaluator
+    /// that doesn't generate exactly one operand.
+    pub fn to_ast(&self) -> Option<AstNode<T, V, E>> {
+        let mut stack = Stack::new();
+        for arithm in self {
+            match *arithm {
+                Arithm::Operand(ref operand) => stack.push(AstNode::Leaf(operand.clone())),
+                Arithm::Variable(ref variable) => stack.push(AstNode::Variable(variable.clone())),
+                Arithm::Evaluator(ref evaluator) => {
+                    if evaluator.operands_generated() != 1 {
+                        return None
+                    }
+                    let mut args = Vec::with_capacity(evaluator.operands_needed());
+                    for _ in 0..evaluator.operands_needed() {
+                        args.push(Box::new(stack.pop()?));
+                    }
+                    args.reverse();
+                    let evaluator = evaluator.clone();
+                    let node = match args.len() {
+                        1 => AstNode::UnaryOp(evaluator, args.pop().unwrap()),
+                        2 => {
+                            let b = args.pop().unwrap();
+                            let a = args.pop().unwrap();
+                            AstNode::BinaryOp(evaluator, a, b)
+                        }
+                        _ => AstNode::NaryOp(evaluator, args),
+                    };
+                    stack.push(node)
+                }
+            }
+        }
+        stack.pop()
+    }
+}
+
+impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
+    /// Constructs an expression from a tree, returns an error if an
+    /// evaluator doesn't have the number of operands it needs.
+    pub fn from_ast(ast: AstNode<T, V, E>) -> Result<Expression<T, V, E>, OperandErr> {
+        ast.append_to(ExpressionBuilder::new()).build()
+    }
+}
+
+#[cfg(all(test, any(feature = "float-evaluator", feature = "int-evaluator")))]
+mod tests {
+    use ast::AstNode;
+    #[cfg(feature = "float-evaluator")]
+    use evaluate::{FloatEvaluator, FloatExpr};
+    #[cfg(feature = "int-evaluator")]
+    use evaluate::{IntEvaluator, IntExpr};
+    #[cfg(feature = "float-evaluator")]
+    use expression::OperandErr;
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn to_ast() {
+        let expr = IntExpr::<i32>::from_iter("3 4 + neg".split_whitespace()).unwrap();
+        let sum = AstNode::BinaryOp(IntEvaluator::Add,
+                                    Box::new(AstNode::Leaf(3)),
+                                    Box::new(AstNode::Leaf(4)));
+        let expected = AstNode::UnaryOp(IntEvaluator::Neg, Box::new(sum));
+        assert_eq!(expr.to_ast(), Some(expected));
+
+        let expr = IntExpr::<i32>::from_iter("one zero +".split_whitespace()).unwrap();
+        let one = Box::new(AstNode::NaryOp(IntEvaluator::One, Vec::new()));
+        let zero = Box::new(AstNode::NaryOp(IntEvaluator::Zero, Vec::new()));
+        assert_eq!(expr.to_ast(), Some(AstNode::BinaryOp(IntEvaluator::Add, one, zero)));
+
+        let expr = IntExpr::<i32>::from_iter("3 4 swap -".split_whitespace()).unwrap();
+        assert_eq!(expr.to_ast(), None);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn ast_round_trip() {
+        let expr_str = "3 4 + 2 * sqrt 1 zero + /";
+        let expr = FloatExpr::<f32>::from_iter(expr_str.split_whitespace()).unwrap();
+        let ast = expr.to_ast().unwrap();
+        let expr = FloatExpr::<f32>::from_ast(ast).unwrap();
+        assert_eq!(expr.to_string(), expr_str);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn invalid_ast() {
+        let ast = AstNode::UnaryOp(FloatEvaluator::Add, Box::new(AstNode::Leaf(3.0)));
+        let res = FloatExpr::<f32>::from_ast(ast);
+        assert_eq!(res, Err(OperandErr::NotEnoughOperand { needed: 2, available: 1 }));
+    }
+}
@@ -0,0 +1,249 @@
+use core::error::Error;
+use core::fmt;
+use alloc::boxed::Box;
+use num_traits::Float;
+use ast::AstNode;
+use evaluate::{Evaluate, FloatEvaluator};
+use expression::Expression;
+
+type Node<T, V> = AstNode<T, V, FloatEvaluator<T>>;
+
+/// Type returned when an expression cannot be differentiated.
+#[derive(Debug, PartialEq)]
+pub enum DerivativeError {
+    /// The expression contains an operator without a known derivative.
+    UnsupportedOperator(&'static str),
+}
+
+impl fmt::Display for DerivativeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DerivativeError::UnsupportedOperator(name) => {
+                write!(f, "cannot differentiate the '{}' operator", name)
+            }
+        }
+    }
+}
+
+impl Error for DerivativeError {}
+
+impl<T: Float, V: Clone + PartialEq> Expression<T, V, FloatEvaluator<T>> {
+    /// Returns the derivative of the expression with respect to the `var` variable,
+    /// the result is simplified when parts of it are constants (cf. `$x 2 pow` gives `2 $x *`).
+    ///
+    /// Supported operators are `+`, `-`, `*`, `/`, `pow`, `neg`, `sqrt`, `exp`, `log2`
+    /// and the constants, a [`DerivativeError`] is returned for the others.
+    ///
+    /// [`DerivativeError`]: ../calculus/enum.DerivativeError.html
+    pub fn derivative(&self, var: V)
+                      -> Result<Expression<T, V, FloatEvaluator<T>>, DerivativeError>
+    {
+        let ast = match self.to_ast() {
+            Some(ast) => ast,
+            None => {
+                let evaluator = self.evaluators().find(|e| e.operands_generated() != 1).unwrap();
+                return Err(DerivativeError::UnsupportedOperator(evaluator.name()))
+            }
+        };
+        let derivative = derive(&ast, &var)?;
+        Ok(Expression::from_ast(derivative).expect("derivatives are valid expressions"))
+    }
+}
+
+fn derive<T, V>(node: &Node<T, V>, var: &V) -> Result<Node<T, V>, DerivativeError>
+    where T: Float,
+          V: Clone + PartialEq
+{
+    use self::FloatEvaluator::*;
+    match *node {
+        AstNode::Leaf(_) => Ok(AstNode::Leaf(T::zero())),
+        AstNode::Variable(ref v) if v == var => Ok(AstNode::Leaf(T::one())),
+        AstNode::Variable(_) => Ok(AstNode::Leaf(T::zero())),
+        AstNode::UnaryOp(evaluator, ref a) => {
+            let da = derive(a, var)?;
+            let a = (**a).clone();
+            match evaluator {
+                Neg => Ok(neg(da)),
+                // sqrt(a)' = a' / (2 * sqrt(a))
+                Sqrt => Ok(div(da, mul(two(), unary(Sqrt, a)))),
+                // exp(a)' = exp(a) * a'
+                Exp => Ok(mul(unary(Exp, a), da)),
+                // log2(a)' = a' / (a * ln(2))
+                Log2 => Ok(div(da, mul(a, AstNode::Leaf(ln_two())))),
+                evaluator => Err(DerivativeError::UnsupportedOperator(evaluator.name())),
+            }
+        }
+        AstNode::BinaryOp(evaluator, ref a, ref b) => {
+            let (da, db) = (derive(a, var)?, derive(b, var)?);
+            let (a, b) = ((**a).clone(), (**b).clone());
+            match evaluator {
+                Add => Ok(add(da, db)),
+                Sub => Ok(sub(da, db)),
+                // (a * b)' = a' * b + a * b'
+                Mul => Ok(add(mul(da, b), mul(a, db))),
+                // (a / b)' = (a' * b - a * b') / b^2
+                Div => Ok(div(sub(mul(da, b.clone()), mul(a, db)), pow(b, two()))),
+                Pow => {
+                    // (a^b)' = b * a^(b - 1) * a' + a^b * ln(a) * b'
+                    let exponent = constant(&db) == Some(T::zero());
+                    let power_rule = mul(b.clone(), mul(pow(a.clone(), sub(b.clone(), one())), da));
+                    if exponent {
+                        return Ok(power_rule)
+                    }
+                    let ln = mul(unary(Log2, a.clone()), AstNode::Leaf(ln_two()));
+                    Ok(add(power_rule, mul(pow(a, b), mul(ln, db))))
+                }
+                evaluator => Err(DerivativeError::UnsupportedOperator(evaluator.name())),
+            }
+        }
+        AstNode::NaryOp(evaluator, ref args) => {
+            match evaluator {
+                Zero | One | Two | NegOne | Half if args.is_empty() => Ok(AstNode::Leaf(T::zero())),
+                evaluator => Err(DerivativeError::UnsupportedOperator(evaluator.name())),
+            }
+        }
+    }
+}
+
+fn constant<T: Float, V>(node: &Node<T, V>) -> Option<T> {
+    match *node {
+        AstNode::Leaf(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn one<T: Float, V>() -> Node<T, V> {
+    AstNode::Leaf(T::one())
+}
+
+fn two<T: Float, V>() -> Node<T, V> {
+    AstNode::Leaf(T::one() + T::one())
+}
+
+fn ln_two<T: Float>() -> T {
+    (T::one() + T::one()).ln()
+}
+
+fn unary<T: Float, V>(evaluator: FloatEvaluator<T>, a: Node<T, V>) -> Node<T, V> {
+    AstNode::UnaryOp(evaluator, Box::new(a))
+}
+
+fn binary<T: Float, V>(evaluator: FloatEvaluator<T>, a: Node<T, V>, b: Node<T, V>) -> Node<T, V> {
+    AstNode::BinaryOp(evaluator, Box::new(a), Box::new(b))
+}
+
+fn neg<T: Float, V>(a: Node<T, V>) -> Node<T, V> {
+    match a {
+        AstNode::Leaf(a) => AstNode::Leaf(-a),
+        AstNode::UnaryOp(FloatEvaluator::Neg, a) => *a,
+        a => unary(FloatEvaluator::Neg, a),
+    }
+}
+
+fn add<T: Float, V>(a: Node<T, V>, b: Node<T, V>) -> Node<T, V> {
+    match (constant(&a), constant(&b)) {
+        (Some(x), Some(y)) => AstNode::Leaf(x + y),
+        (Some(x), _) if x.is_zero() => b,
+        (_, Some(y)) if y.is_zero() => a,
+        _ => binary(FloatEvaluator::Add, a, b),
+    }
+}
+
+fn sub<T: Float, V>(a: Node<T, V>, b: Node<T, V>) -> Node<T, V> {
+    match (constant(&a), constant(&b)) {
+        (Some(x), Some(y)) => AstNode::Leaf(x - y),
+        (Some(x), _) if x.is_zero() => neg(b),
+        (_, Some(y)) if y.is_zero() => a,
+        _ => binary(FloatEvaluator::Sub, a, b),
+    }
+}
+
+fn mul<T: Float, V>(a: Node<T, V>, b: Node<T, V>) -> Node<T, V> {
+    match (constant(&a), constant(&b)) {
+        (Some(x), Some(y)) => AstNode::Leaf(x * y),
+        (Some(x), _) | (_, Some(x)) if x.is_zero() => AstNode::Leaf(T::zero()),
+        (Some(x), _) if x.is_one() => b,
+        (_, Some(y)) if y.is_one() => a,
+        _ => binary(FloatEvaluator::Mul, a, b),
+    }
+}
+
+fn div<T: Float, V>(a: Node<T, V>, b: Node<T, V>) -> Node<T, V> {
+    match (constant(&a), constant(&b)) {
+        (Some(x), Some(y)) => AstNode::Leaf(x / y),
+        (Some(x), _) if x.is_zero() => AstNode::Leaf(T::zero()),
+        (_, Some(y)) if y.is_one() => a,
+        _ => binary(FloatEvaluator::Div, a, b),
+    }
+}
+
+fn pow<T: Float, V>(a: Node<T, V>, b: Node<T, V>) -> Node<T, V> {
+    match (constant(&a), constant(&b)) {
+        (Some(x), Some(y)) => AstNode::Leaf(x.powf(y)),
+        (_, Some(y)) if y.is_zero() => AstNode::Leaf(T::one()),
+        (_, Some(y)) if y.is_one() => a,
+        _ => binary(FloatEvaluator::Pow, a, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use calculus::DerivativeError;
+    use convert_ref::TryFromRef;
+    use evaluate::{FloatExpr, VariableFloatExprNamed};
+    use variable::{DummyVariable, NamedVar};
+
+    fn parse(expr: &str) -> VariableFloatExprNamed<f64> {
+        VariableFloatExprNamed::<f64>::from_iter(expr.split_whitespace()).unwrap()
+    }
+
+    fn x() -> NamedVar {
+        NamedVar::try_from_ref(&"$x").unwrap()
+    }
+
+    #[test]
+    fn power_rule() {
+        assert_eq!(parse("$x 2 pow").derivative(x()), Ok(parse("2 $x *")));
+        assert_eq!(parse("$x 3 pow").derivative(x()), Ok(parse("3 $x 2 pow *")));
+    }
+
+    #[test]
+    fn exponential() {
+        assert_eq!(parse("$x exp").derivative(x()), Ok(parse("$x exp")));
+        assert_eq!(parse("$x 2 * exp").derivative(x()), Ok(parse("$x 2 * exp 2 *")));
+    }
+
+    #[test]
+    fn product_and_quotient_rules() {
+        let mut variables = BTreeMap::new();
+        variables.insert("x".to_string(), 3.0);
+
+        let derivative = parse("$x $x * $x +").derivative(x()).unwrap();
+        assert_eq!(derivative.evaluate_with_variables(&variables), Ok(7.0));
+
+        let derivative = parse("1 $x /").derivative(x()).unwrap();
+        let value = derivative.evaluate_with_variables(&variables).unwrap();
+        assert!((value + 1.0 / 9.0).abs() < 1e-12);
+
+        let derivative = parse("2 $x pow").derivative(x()).unwrap();
+        let value = derivative.evaluate_with_variables(&variables).unwrap();
+        assert!((value - 8.0 * 2.0_f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(parse("$y 4 *").derivative(x()), Ok(parse("0")));
+        let expr = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        assert_eq!(expr.derivative(DummyVariable).unwrap().evaluate(), Ok(0.0));
+    }
+
+    #[test]
+    fn unsupported_operator() {
+        let res = parse("$x round").derivative(x());
+        assert_eq!(res, Err(DerivativeError::UnsupportedOperator("round")));
+
+        let res = parse("$x 2 swap -").derivative(x());
+        assert_eq!(res, Err(DerivativeError::UnsupportedOperator("swap")));
+    }
+}
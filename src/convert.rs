@@ -0,0 +1,341 @@
+use core::error::Error;
+use core::fmt;
+use core::iter::Peekable;
+use core::str::Chars;
+use alloc::vec::Vec;
+use alloc::string::{String, ToString};
+
+/// Type returned when an infix expression cannot be converted.
+#[derive(Debug, PartialEq)]
+pub enum ShuntingYardError {
+    UnmatchedParenthesis,
+    UnknownOperator(char),
+}
+
+impl fmt::Display for ShuntingYardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShuntingYardError::UnmatchedParenthesis => f.write_str("unmatched parenthesis"),
+            ShuntingYardError::UnknownOperator(op) => write!(f, "unknown operator: '{}'", op),
+        }
+    }
+}
+
+impl Error for ShuntingYardError {}
+
+/// Type returned when a `RPN` expression cannot be converted.
+#[derive(Debug, PartialEq)]
+pub enum RpnToInfixError {
+    EmptyExpression,
+    StackUnderflow,
+    TooManyOperands,
+}
+
+impl fmt::Display for RpnToInfixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RpnToInfixError::EmptyExpression => f.write_str("empty expression"),
+            RpnToInfixError::StackUnderflow => f.write_str("not enough operands on the stack"),
+            RpnToInfixError::TooManyOperands => f.write_str("too many operands left on the stack"),
+        }
+    }
+}
+
+impl Error for RpnToInfixError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Operand(String),
+    Function(String),
+    Operator(char),
+    Neg,
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl Token {
+    fn precedence(&self) -> usize {
+        match *self {
+            Token::Operator('+') | Token::Operator('-') => 1,
+            Token::Operator('^') => 4,
+            Token::Operator(_) => 2,
+            Token::Neg => 3,
+            _ => 0,
+        }
+    }
+
+    fn is_right_associative(&self) -> bool {
+        matches!(*self, Token::Operator('^') | Token::Neg)
+    }
+
+    fn into_rpn(self) -> String {
+        match self {
+            Token::Operand(name) | Token::Function(name) => name,
+            Token::Operator('^') => "pow".to_string(),
+            Token::Operator(op) => op.to_string(),
+            Token::Neg => "neg".to_string(),
+            Token::LeftParen | Token::RightParen | Token::Comma => unreachable!(),
+        }
+    }
+}
+
+fn take_while<F>(chars: &mut Peekable<Chars>, predicate: F) -> String
+    where F: Fn(char) -> bool
+{
+    let mut string = String::new();
+    while let Some(&c) = chars.peek() {
+        if !predicate(c) {
+            break
+        }
+        string.push(c);
+        chars.next();
+    }
+    string
+}
+
+fn tokenize(infix: &str) -> Result<Vec<Token>, ShuntingYardError> {
+    let mut tokens = Vec::new();
+    let mut expect_operand = true;
+    let mut chars = infix.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                Token::Operand(take_while(&mut chars, |c| c.is_ascii_digit() || c == '.'))
+            }
+            c if c.is_alphabetic() || c == '$' || c == '_' => {
+                let name = take_while(&mut chars, |c| c.is_alphanumeric() || c == '$' || c == '_');
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                match chars.peek() {
+                    Some(&'(') => Token::Function(name),
+                    _ => Token::Operand(name),
+                }
+            }
+            _ => {
+                chars.next();
+                match c {
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    ',' => Token::Comma,
+                    '+' if expect_operand => continue,
+                    '-' if expect_operand => Token::Neg,
+                    '+' | '-' | '*' | '/' | '%' | '^' => Token::Operator(c),
+                    _ => return Err(ShuntingYardError::UnknownOperator(c)),
+                }
+            }
+        };
+        expect_operand = !matches!(token, Token::Operand(_) | Token::RightParen);
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Converts an infix expression (cf. `(3 + 4) * 2`) to a space separated
+/// [`Reverse Polish notated`] one (cf. `3 4 + 2 *`), using the [`shunting-yard`] algorithm.
+///
+/// Handles the `+`, `-`, `*`, `/`, `%` and `^` (converted to `pow`) binary operators,
+/// the unary minus (converted to `neg`) and functions calls like `sqrt(9)` or `pow(2, 3)`.
+///
+/// ```
+/// use ripin::convert::infix_to_rpn;
+///
+/// assert_eq!(infix_to_rpn("(3 + 4) * 2"), Ok("3 4 + 2 *".to_string()));
+/// assert_eq!(infix_to_rpn("sqrt(9) - 2^3"), Ok("9 sqrt 2 3 pow -".to_string()));
+/// ```
+///
+/// [`Reverse Polish notated`]: https://en.wikipedia.org/wiki/Reverse_Polish_notation
+/// [`shunting-yard`]: https://en.wikipedia.org/wiki/Shunting-yard_algorithm
+pub fn infix_to_rpn(infix: &str) -> Result<String, ShuntingYardError> {
+    let mut output = Vec::new();
+    let mut operators: Vec<Token> = Vec::new();
+    for token in tokenize(infix)? {
+        match token {
+            Token::Operand(_) => output.push(token.into_rpn()),
+            Token::Function(_) | Token::Neg | Token::LeftParen => operators.push(token),
+            Token::Operator(_) => {
+                while let Some(top) = operators.pop() {
+                    let pops = match top {
+                        Token::Operator(_) | Token::Neg => {
+                            top.precedence() > token.precedence() ||
+                            (top.precedence() == token.precedence() && !token.is_right_associative())
+                        }
+                        _ => false,
+                    };
+                    if !pops {
+                        operators.push(top);
+                        break
+                    }
+                    output.push(top.into_rpn());
+                }
+                operators.push(token)
+            }
+            Token::Comma | Token::RightParen => {
+                loop {
+                    match operators.pop() {
+                        Some(Token::LeftParen) => break,
+                        Some(top) => output.push(top.into_rpn()),
+                        None => return Err(ShuntingYardError::UnmatchedParenthesis),
+                    }
+                }
+                if token == Token::Comma {
+                    operators.push(Token::LeftParen);
+                } else if let Some(&Token::Function(_)) = operators.last() {
+                    output.push(operators.pop().unwrap().into_rpn());
+                }
+            }
+        }
+    }
+    while let Some(top) = operators.pop() {
+        match top {
+            Token::LeftParen => return Err(ShuntingYardError::UnmatchedParenthesis),
+            _ => output.push(top.into_rpn()),
+        }
+    }
+    Ok(output.join(" "))
+}
+
+/// The precedence given to operands and function calls,
+/// they never need to be parenthesized.
+const ATOM_PRECEDENCE: usize = 5;
+
+/// Converts a space separated [`Reverse Polish notated`] expression (cf. `3 4 + 2 *`)
+/// to an infix one using the least parentheses possible (cf. `(3 + 4) * 2`).
+///
+/// The `+`, `-`, `*`, `/`, `%` and `pow` (converted to `^`) binary operators and
+/// the `neg` (converted to an unary minus), `sqrt`, `log2`, `exp` and `round` unary
+/// operators are understood, any other token is considered as an operand.
+///
+/// ```
+/// use ripin::convert::rpn_to_infix;
+///
+/// assert_eq!(rpn_to_infix("3 4 + 2 *"), Ok("(3 + 4) * 2".to_string()));
+/// assert_eq!(rpn_to_infix("9 sqrt 2 3 pow -"), Ok("sqrt(9) - 2 ^ 3".to_string()));
+/// ```
+///
+/// [`Reverse Polish notated`]: https://en.wikipedia.org/wiki/Reverse_Polish_notation
+pub fn rpn_to_infix(rpn: &str) -> Result<String, RpnToInfixError> {
+    let mut stack: Vec<(String, usize)> = Vec::new();
+    for token in rpn.split_whitespace() {
+        let binary_prec = match token {
+            "+" | "-" => Some(1),
+            "*" | "/" | "%" => Some(2),
+            "pow" => Some(4),
+            _ => None,
+        };
+        match (token, binary_prec) {
+            (_, Some(prec)) => {
+                let (rhs, rhs_prec) = stack.pop().ok_or(RpnToInfixError::StackUnderflow)?;
+                let (lhs, lhs_prec) = stack.pop().ok_or(RpnToInfixError::StackUnderflow)?;
+                let right_assoc = token == "pow";
+                let lhs = if lhs_prec < prec || (right_assoc && lhs_prec == prec) {
+                    format!("({})", lhs)
+                } else {
+                    lhs
+                };
+                let rhs = if rhs_prec < prec || (!right_assoc && rhs_prec == prec) {
+                    format!("({})", rhs)
+                } else {
+                    rhs
+                };
+                let op = if right_assoc { "^" } else { token };
+                stack.push((format!("{} {} {}", lhs, op, rhs), prec))
+            }
+            ("neg", None) => {
+                let (operand, prec) = stack.pop().ok_or(RpnToInfixError::StackUnderflow)?;
+                if prec < 3 {
+                    stack.push((format!("-({})", operand), 3))
+                } else {
+                    stack.push((format!("-{}", operand), 3))
+                }
+            }
+            ("sqrt", None) | ("log2", None) | ("exp", None) | ("round", None) => {
+                let (operand, _) = stack.pop().ok_or(RpnToInfixError::StackUnderflow)?;
+                stack.push((format!("{}({})", token, operand), ATOM_PRECEDENCE))
+            }
+            _ => stack.push((token.to_string(), ATOM_PRECEDENCE)),
+        }
+    }
+    match stack.len() {
+        0 => Err(RpnToInfixError::EmptyExpression),
+        1 => Ok(stack.pop().unwrap().0),
+        _ => Err(RpnToInfixError::TooManyOperands),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use convert::{infix_to_rpn, rpn_to_infix, ShuntingYardError, RpnToInfixError};
+
+    #[test]
+    fn simple_infix_to_rpn() {
+        assert_eq!(infix_to_rpn("(3 + 4) * 2"), Ok("3 4 + 2 *".to_string()));
+        assert_eq!(infix_to_rpn("2^3"), Ok("2 3 pow".to_string()));
+        assert_eq!(infix_to_rpn("3 + 4 * 2"), Ok("3 4 2 * +".to_string()));
+        assert_eq!(infix_to_rpn("10 - 4 - 3"), Ok("10 4 - 3 -".to_string()));
+        assert_eq!(infix_to_rpn("2 ^ 3 ^ 2"), Ok("2 3 2 pow pow".to_string()));
+    }
+
+    #[test]
+    fn unary_and_functions() {
+        assert_eq!(infix_to_rpn("-2^2"), Ok("2 2 pow neg".to_string()));
+        assert_eq!(infix_to_rpn("3 * -(4 + 1)"), Ok("3 4 1 + neg *".to_string()));
+        assert_eq!(infix_to_rpn("sqrt(9) + $0"), Ok("9 sqrt $0 +".to_string()));
+        assert_eq!(infix_to_rpn("pow(2, 1 + 2)"), Ok("2 1 2 + pow".to_string()));
+    }
+
+    #[test]
+    fn invalid_infix() {
+        assert_eq!(infix_to_rpn("(3 + 4"), Err(ShuntingYardError::UnmatchedParenthesis));
+        assert_eq!(infix_to_rpn("3 + 4)"), Err(ShuntingYardError::UnmatchedParenthesis));
+        assert_eq!(infix_to_rpn("3 & 4"), Err(ShuntingYardError::UnknownOperator('&')));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn evaluate_converted_expression() {
+        use evaluate::FloatExpr;
+
+        let rpn = infix_to_rpn("(3 + 4) * 2 - sqrt(16)").unwrap();
+        let expr = FloatExpr::<f32>::from_iter(rpn.split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(10.0));
+    }
+
+    #[test]
+    fn simple_rpn_to_infix() {
+        assert_eq!(rpn_to_infix("3 4 +"), Ok("3 + 4".to_string()));
+        assert_eq!(rpn_to_infix("3 4 + 2 *"), Ok("(3 + 4) * 2".to_string()));
+        assert_eq!(rpn_to_infix("9 sqrt"), Ok("sqrt(9)".to_string()));
+        assert_eq!(rpn_to_infix("2 3 4 + *"), Ok("2 * (3 + 4)".to_string()));
+        assert_eq!(rpn_to_infix("10 4 3 - -"), Ok("10 - (4 - 3)".to_string()));
+        assert_eq!(rpn_to_infix("10 4 - 3 -"), Ok("10 - 4 - 3".to_string()));
+        assert_eq!(rpn_to_infix("2 3 pow 2 pow"), Ok("(2 ^ 3) ^ 2".to_string()));
+        assert_eq!(rpn_to_infix("3 4 + neg"), Ok("-(3 + 4)".to_string()));
+    }
+
+    #[test]
+    fn invalid_rpn() {
+        assert_eq!(rpn_to_infix(""), Err(RpnToInfixError::EmptyExpression));
+        assert_eq!(rpn_to_infix("3 +"), Err(RpnToInfixError::StackUnderflow));
+        assert_eq!(rpn_to_infix("3 4"), Err(RpnToInfixError::TooManyOperands));
+    }
+
+    #[test]
+    fn infix_round_trip() {
+        for infix in &["(3 + 4) * 2", "2 ^ 3 ^ 2", "-(1 - 2) / sqrt($0)"] {
+            let rpn = infix_to_rpn(infix).unwrap();
+            assert_eq!(&rpn_to_infix(&rpn).unwrap(), infix);
+        }
+    }
+
+    #[test]
+    fn display_errors() {
+        assert_eq!(format!("{}", ShuntingYardError::UnknownOperator('&')), "unknown operator: '&'");
+        assert_eq!(format!("{}", RpnToInfixError::EmptyExpression), "empty expression");
+    }
+}
@@ -1,10 +1,14 @@
+use core::num::Wrapping;
+use num_traits::Num;
+use num_complex::{Complex, ParseComplexError};
+
 /// Attempt to construct `Self` via a conversion from a ref.
 pub trait TryFromRef<T>: Sized {
     /// The type returned in the event of a conversion error.
     type Err;
 
     /// Performs the conversion.
-    fn try_from_ref(&T) -> Result<Self, Self::Err>;
+    fn try_from_ref(value: &T) -> Result<Self, Self::Err>;
 }
 
 /// An attempted conversion that don't consumes `self`, which may or may not be expensive.
@@ -29,13 +33,13 @@ impl<T, U> TryIntoRef<U> for T
     type Err = U::Err;
 
     fn try_into_ref(&self) -> Result<U, U::Err> {
-        U::try_from_ref(&self)
+        U::try_from_ref(self)
     }
 }
 
 macro_rules! implement_try_from_ref {
     ( $($x:ty) * ) => {
-        use std::str::FromStr;
+        use core::str::FromStr;
         $(
             impl<'a> TryFromRef<&'a str> for $x {
                 type Err = <$x as FromStr>::Err;
@@ -48,4 +52,53 @@ macro_rules! implement_try_from_ref {
     };
 }
 
-implement_try_from_ref!(f32 f64 isize i8 i16 i32 i64 usize u8 u16 u32 u64);
+implement_try_from_ref!(f32 f64 isize i8 i16 i32 i64 i128 usize u8 u16 u32 u64 u128 bool);
+
+impl<'a, T: FromStr> TryFromRef<&'a str> for Wrapping<T> {
+    type Err = T::Err;
+
+    fn try_from_ref(s: &&'a str) -> Result<Self, Self::Err> {
+        T::from_str(s).map(Wrapping)
+    }
+}
+
+impl<'a, T: FromStr + Num + Clone> TryFromRef<&'a str> for Complex<T> {
+    type Err = ParseComplexError<T::Err>;
+
+    fn try_from_ref(s: &&'a str) -> Result<Self, Self::Err> {
+        FromStr::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use convert_ref::TryFromRef;
+    #[cfg(feature = "int-evaluator")]
+    use evaluate::{IntExpr, UintExpr};
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn parse_128_bits_integers() {
+        let tokens = vec!["170141183460469231731687303715884105727"];
+        let expr = IntExpr::<i128>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(i128::MAX));
+
+        let expr = UintExpr::<u128>::from_iter("2 127 pow".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1 << 127));
+    }
+
+    #[test]
+    fn parse_wrapping() {
+        use core::num::Wrapping;
+
+        assert_eq!(Wrapping::<i8>::try_from_ref(&"-128"), Ok(Wrapping(-128)));
+        assert!(Wrapping::<i8>::try_from_ref(&"128").is_err());
+    }
+
+    #[test]
+    fn parse_bool() {
+        assert_eq!(bool::try_from_ref(&"true"), Ok(true));
+        assert_eq!(bool::try_from_ref(&"false"), Ok(false));
+        assert!(bool::try_from_ref(&"TRUE").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use core::marker::PhantomData;
+use core::error::Error;
+use core::fmt;
+use num_complex::Complex;
+use num_traits::{Float, Zero};
+use evaluate::Evaluate;
+use stack::Stack;
+use {pop_two_operands, StackUnderflowError};
+use convert_ref::TryFromRef;
+
+/// Basic Complex Evaluator working on [`Complex`] operands of any [`Float`] type.
+///
+/// Operations producing a real number (like `"abs"` or `"re"`)
+/// push a `Complex` with a zero imaginary part.
+///
+/// [`Complex`]: https://docs.rs/num-complex/0.4/num_complex/struct.Complex.html
+/// [`Float`]: https://docs.rs/num-traits/0.2/num_traits/float/trait.Float.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ComplexEvaluator<T: Float> {
+    /// `"+"` will pop `2` operands and push `1`.
+    Add,
+    /// `"-"` will pop `2` operands and push `1`.
+    Sub,
+    /// `"*"` will pop `2` operands and push `1`.
+    Mul,
+    /// `"/"` will pop `2` operands and push `1`.
+    Div,
+    /// `"conj"` will pop `1` operand and push `1`.
+    Conj,
+    /// `"abs"` will pop `1` operand and push `1`.
+    Abs,
+    /// `"arg"` will pop `1` operand and push `1`.
+    Arg,
+    /// `"re"` will pop `1` operand and push `1`.
+    Re,
+    /// `"im"` will pop `1` operand and push `1`.
+    Im,
+    /// `"polar"` will pop `2` operands (magnitude and angle) and push `1`.
+    Polar,
+    #[doc(hidden)]
+    _Phantom(PhantomData<T>),
+}
+
+/// Type returned when an error occurs on complex operation.
+#[derive(Debug, PartialEq)]
+pub enum ComplexEvaluateErr {
+    DivByZero,
+    StackUnderflow,
+}
+
+impl fmt::Display for ComplexEvaluateErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ComplexEvaluateErr::DivByZero => f.write_str("complex division by zero"),
+            ComplexEvaluateErr::StackUnderflow => StackUnderflowError.fmt(f),
+        }
+    }
+}
+
+impl Error for ComplexEvaluateErr {}
+
+impl From<StackUnderflowError> for ComplexEvaluateErr {
+    fn from(_: StackUnderflowError) -> Self {
+        ComplexEvaluateErr::StackUnderflow
+    }
+}
+
+fn real<T: Float>(re: T) -> Complex<T> {
+    Complex::new(re, T::zero())
+}
+
+impl<T: Float> Evaluate<Complex<T>> for ComplexEvaluator<T> {
+    type Err = ComplexEvaluateErr;
+
+    fn operands_needed(&self) -> usize {
+        use self::ComplexEvaluator::*;
+        match *self {
+            Add | Sub | Mul | Div | Polar => 2,
+            Conj | Abs | Arg | Re | Im => 1,
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    fn operands_generated(&self) -> usize {
+        use self::ComplexEvaluator::*;
+        match *self {
+            Add | Sub | Mul | Div | Polar | Conj | Abs | Arg | Re | Im => 1,
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        use self::ComplexEvaluator::*;
+        match *self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Conj => "conj",
+            Abs => "abs",
+            Arg => "arg",
+            Re => "re",
+            Im => "im",
+            Polar => "polar",
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    #[allow(clippy::unit_arg)]
+    fn evaluate(self, stack: &mut Stack<Complex<T>>) -> Result<(), Self::Err> {
+        use self::ComplexEvaluator::*;
+        match self {
+            Add => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(a + b))
+            }
+            Sub => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(a - b))
+            }
+            Mul => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(a * b))
+            }
+            Div => {
+                let (a, b) = pop_two_operands(stack)?;
+                if b.is_zero() {
+                    Err(ComplexEvaluateErr::DivByZero)
+                } else {
+                    Ok(stack.push(a / b))
+                }
+            }
+            Conj => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(a.conj()))
+            }
+            Abs => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(real(a.norm())))
+            }
+            Arg => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(real(a.arg())))
+            }
+            Re => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(real(a.re)))
+            }
+            Im => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(real(a.im)))
+            }
+            Polar => {
+                let (r, theta) = pop_two_operands(stack)?;
+                Ok(stack.push(Complex::from_polar(r.re, theta.re)))
+            }
+            _Phantom(_) => unreachable!(),
+        }
+    }
+}
+
+/// Type returned when a conversion cannot be performed.
+#[derive(Debug)]
+pub enum ComplexErr<'a> {
+    InvalidExpr(&'a str),
+}
+
+impl<'a> fmt::Display for ComplexErr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ComplexErr::InvalidExpr(expr) => write!(f, "unknown complex operator: '{}'", expr),
+        }
+    }
+}
+
+impl<'a> Error for ComplexErr<'a> {}
+
+implement_arithmetic_operators!(ComplexEvaluator where T: Float);
+
+impl<'a, T: Float> TryFromRef<&'a str> for ComplexEvaluator<T> {
+    type Err = ComplexErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        use self::ComplexEvaluator::*;
+        match *expr {
+            "+" => Ok(Add),
+            "-" => Ok(Sub),
+            "*" => Ok(Mul),
+            "/" => Ok(Div),
+            "conj" => Ok(Conj),
+            "abs" => Ok(Abs),
+            "arg" => Ok(Arg),
+            "re" => Ok(Re),
+            "im" => Ok(Im),
+            "polar" => Ok(Polar),
+            _ => Err(ComplexErr::InvalidExpr(expr)),
+        }
+    }
+}
+
+impl<T: Float> fmt::Display for ComplexEvaluator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(Evaluate::<Complex<T>>::name(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+    use expression::ExprResult;
+    use evaluate::{ComplexErr, ComplexEvaluateErr, ComplexExpr};
+
+    #[test]
+    fn bad_operator() {
+        let expr_str = "1+2i 3+4i + sqrt";
+        let tokens = expr_str.split_whitespace();
+        let res = ComplexExpr::<f64>::from_iter(tokens);
+        match res {
+            Err(ExprResult::InvalidToken { evaluator: ComplexErr::InvalidExpr("sqrt"), .. }) => (),
+            _ => panic!("{:?}", res),
+        }
+    }
+
+    #[test]
+    fn simple_addition() {
+        let expr_str = "1+2i 3+4i +";
+        let tokens = expr_str.split_whitespace();
+        let expr = ComplexExpr::<f64>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Complex::new(4.0, 6.0)));
+    }
+
+    #[test]
+    fn simple_multiplication() {
+        let expr_str = "1+2i 3-4i *";
+        let tokens = expr_str.split_whitespace();
+        let expr = ComplexExpr::<f64>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Complex::new(11.0, 2.0)));
+    }
+
+    #[test]
+    fn invalid_division() {
+        let expr_str = "1+2i 0 /";
+        let tokens = expr_str.split_whitespace();
+        let expr = ComplexExpr::<f64>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(ComplexEvaluateErr::DivByZero));
+    }
+
+    #[test]
+    fn real_results() {
+        let expr = ComplexExpr::<f64>::from_iter("3+4i abs".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Complex::new(5.0, 0.0)));
+
+        let expr = ComplexExpr::<f64>::from_iter("3+4i im".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Complex::new(4.0, 0.0)));
+
+        let expr = ComplexExpr::<f64>::from_iter("3+4i conj re".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Complex::new(3.0, 0.0)));
+    }
+
+    #[test]
+    fn polar_form() {
+        let expr_str = "3+4i abs 3+4i arg polar";
+        let tokens = expr_str.split_whitespace();
+        let expr = ComplexExpr::<f64>::from_iter(tokens).unwrap();
+        let res = expr.evaluate().unwrap();
+        assert!((res - Complex::new(3.0, 4.0)).norm() < 1e-10);
+    }
+
+    #[test]
+    fn variable_expression() {
+        use evaluate::VariableComplexExpr;
+        use variable::IndexVar;
+
+        let expr_str = "$0 $1 -";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableComplexExpr::<f64, IndexVar>::from_iter(tokens).unwrap();
+        let variables = vec![Complex::new(1.0, 1.0), Complex::new(0.5, 2.0)];
+        assert_eq!(expr.evaluate_with_variables(&variables), Ok(Complex::new(0.5, -1.0)));
+    }
+}
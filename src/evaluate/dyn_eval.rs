@@ -0,0 +1,281 @@
+use core::error::Error;
+use core::fmt;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use evaluate::Evaluate;
+use expression::{Expression, ExpressionBuilder, ExprResult};
+use convert_ref::{TryFromRef, TryIntoRef};
+use stack::Stack;
+
+/// An object safe version of [`Evaluate`], implemented for every
+/// `Copy` evaluator whose error implements [`Error`].
+///
+/// [`Evaluate`]: ../evaluate/trait.Evaluate.html
+/// [`Error`]: https://doc.rust-lang.org/std/error/trait.Error.html
+pub trait DynEvaluate<T> {
+    /// Returns the number of operand this `Evaluator` needs.
+    fn operands_needed(&self) -> usize;
+
+    /// Returns the number of operand this `Evaluator` will generate.
+    fn operands_generated(&self) -> usize;
+
+    /// Returns a short identifier of this `Evaluator`.
+    fn name(&self) -> &'static str;
+
+    /// Execute the evaluation with the given `stack`, the error is boxed.
+    fn evaluate_dyn(&self, stack: &mut Stack<T>) -> Result<(), Box<dyn Error>>;
+}
+
+impl<T, E> DynEvaluate<T> for E
+    where E: Evaluate<T> + Copy,
+          E::Err: Error + 'static
+{
+    fn operands_needed(&self) -> usize {
+        Evaluate::operands_needed(self)
+    }
+
+    fn operands_generated(&self) -> usize {
+        Evaluate::operands_generated(self)
+    }
+
+    fn name(&self) -> &'static str {
+        Evaluate::name(self)
+    }
+
+    fn evaluate_dyn(&self, stack: &mut Stack<T>) -> Result<(), Box<dyn Error>> {
+        self.evaluate(stack).map_err(Box::from)
+    }
+}
+
+/// Evaluator that dispatches at runtime to any borrowed [`DynEvaluate`],
+/// allowing evaluators of different types in the same expression.
+///
+/// The evaluator is borrowed for the expression to stay `Copy`,
+/// like the evaluators expressions need to be evaluated.
+///
+/// ```
+/// # #[cfg(feature = "float-evaluator")] {
+/// use ripin::evaluate::{DynEvaluator, DynExpr, FloatEvaluator};
+/// use ripin::expression::ExpressionBuilder;
+///
+/// let add = FloatEvaluator::<f64>::Add;
+/// let neg = FloatEvaluator::<f64>::Neg;
+/// let expr: DynExpr<f64> = ExpressionBuilder::new()
+///     .push_operand(3.0)
+///     .push_operand(4.0)
+///     .push_evaluator(DynEvaluator::new(&add))
+///     .push_evaluator(DynEvaluator::new(&neg))
+///     .build()
+///     .unwrap();
+/// assert_eq!(expr.evaluate().unwrap(), -7.0);
+/// # }
+/// ```
+///
+/// [`DynEvaluate`]: trait.DynEvaluate.html
+pub struct DynEvaluator<'a, T: 'a>(&'a dyn DynEvaluate<T>);
+
+impl<'a, T> DynEvaluator<'a, T> {
+    /// Wraps a reference to any evaluator.
+    pub fn new(evaluator: &'a dyn DynEvaluate<T>) -> Self {
+        DynEvaluator(evaluator)
+    }
+}
+
+impl<'a, T> Clone for DynEvaluator<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for DynEvaluator<'a, T> {}
+
+impl<'a, T> Evaluate<T> for DynEvaluator<'a, T> {
+    type Err = Box<dyn Error>;
+
+    fn operands_needed(&self) -> usize {
+        self.0.operands_needed()
+    }
+
+    fn operands_generated(&self) -> usize {
+        self.0.operands_generated()
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
+        self.0.evaluate_dyn(stack)
+    }
+}
+
+impl<'a, T> fmt::Debug for DynEvaluator<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("DynEvaluator").field(&self.0.name()).finish()
+    }
+}
+
+impl<'a, T> fmt::Display for DynEvaluator<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0.name())
+    }
+}
+
+/// Registry of [`DynEvaluator`]s indexed by their token,
+/// used to parse expressions of evaluators only known at runtime.
+///
+/// [`DynEvaluator`]: struct.DynEvaluator.html
+pub struct DynEvaluators<'a, T: 'a> {
+    evaluators: BTreeMap<&'a str, DynEvaluator<'a, T>>,
+}
+
+impl<'a, T> Default for DynEvaluators<'a, T> {
+    fn default() -> Self {
+        DynEvaluators { evaluators: BTreeMap::new() }
+    }
+}
+
+impl<'a, T> DynEvaluators<'a, T> {
+    /// Creates a registry without any evaluator.
+    pub fn new() -> Self {
+        DynEvaluators::default()
+    }
+
+    /// Registers an evaluator for the given token,
+    /// returns the evaluator previously registered for it.
+    pub fn register(&mut self, token: &'a str, evaluator: &'a dyn DynEvaluate<T>)
+                    -> Option<DynEvaluator<'a, T>>
+    {
+        self.evaluators.insert(token, DynEvaluator::new(evaluator))
+    }
+
+    /// Returns the evaluator registered for the given token.
+    pub fn get(&self, token: &str) -> Option<DynEvaluator<'a, T>> {
+        self.evaluators.get(token).cloned()
+    }
+
+    /// Parses an expression like [`Expression::from_iter()`] does, tokens are
+    /// looked up in the registry before being parsed as variables and operands.
+    /// Unknown tokens are returned as the evaluator error.
+    ///
+    /// [`Expression::from_iter()`]: ../expression/struct.Expression.html#method.from_iter
+    #[allow(clippy::type_complexity)]
+    pub fn parse<A, V, I>(&self, iter: I)
+                          -> Result<Expression<T, V, DynEvaluator<'a, T>>,
+                                    ExprResult<String,
+                                               <V as TryFromRef<A>>::Err,
+                                               <T as TryFromRef<A>>::Err>>
+        where A: AsRef<str>,
+              T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              I: IntoIterator<Item=A>
+    {
+        let mut builder = ExpressionBuilder::new();
+        for (position, token) in iter.into_iter().enumerate() {
+            if let Some(evaluator) = self.get(token.as_ref()) {
+                builder = builder.push_evaluator(evaluator);
+                continue
+            }
+            builder = match TryIntoRef::<V>::try_into_ref(&token) {
+                Ok(var) => builder.push_variable(var),
+                Err(var_err) => {
+                    match TryIntoRef::<T>::try_into_ref(&token) {
+                        Ok(op) => builder.push_operand(op),
+                        Err(op_err) => return Err(ExprResult::InvalidToken {
+                            position,
+                            evaluator: token.as_ref().to_string(),
+                            variable: var_err,
+                            operand: op_err,
+                        }),
+                    }
+                }
+            };
+        }
+        builder.build().map_err(ExprResult::OperandErr)
+    }
+}
+
+#[cfg(all(test, feature = "float-evaluator"))]
+mod tests {
+    use core::error::Error;
+    use core::fmt;
+    use alloc::string::ToString;
+    use evaluate::{DynEvaluators, DynExpr, Evaluate, FloatEvaluator, StrictFloatEvaluator};
+    use expression::ExprResult;
+    use stack::Stack;
+    use variable::DummyVariable;
+
+    #[derive(Debug)]
+    struct NegativeSquareRoot(f64);
+
+    impl fmt::Display for NegativeSquareRoot {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "square root of the negative number {}", self.0)
+        }
+    }
+
+    impl Error for NegativeSquareRoot {}
+
+    #[derive(Debug, Copy, Clone)]
+    struct CheckedSqrt;
+
+    impl Evaluate<f64> for CheckedSqrt {
+        type Err = NegativeSquareRoot;
+
+        fn operands_needed(&self) -> usize {
+            1
+        }
+
+        fn operands_generated(&self) -> usize {
+            1
+        }
+
+        fn name(&self) -> &'static str {
+            "csqrt"
+        }
+
+        #[allow(clippy::unit_arg)]
+        fn evaluate(self, stack: &mut Stack<f64>) -> Result<(), Self::Err> {
+            let a = stack.pop().unwrap();
+            if a < 0.0 {
+                return Err(NegativeSquareRoot(a))
+            }
+            Ok(stack.push(a.sqrt()))
+        }
+    }
+
+    #[test]
+    fn mixed_evaluators() {
+        let add = FloatEvaluator::<f64>::Add;
+        let div = StrictFloatEvaluator(FloatEvaluator::<f64>::Div);
+        let mut evaluators = DynEvaluators::new();
+        evaluators.register("+", &add);
+        evaluators.register("/", &div);
+        evaluators.register("csqrt", &CheckedSqrt);
+
+        let expr: DynExpr<f64> = evaluators.parse("12 4 + csqrt 2 /".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate().unwrap(), 2.0);
+        assert_eq!(expr.to_string(), "12 4 + csqrt 2 /");
+
+        let expr: DynExpr<f64> = evaluators.parse("-4 csqrt".split_whitespace()).unwrap();
+        let err = expr.evaluate().unwrap_err();
+        assert_eq!(err.to_string(), "square root of the negative number -4");
+
+        let expr: DynExpr<f64> = evaluators.parse("1 0 /".split_whitespace()).unwrap();
+        let err = expr.evaluate().unwrap_err();
+        assert_eq!(err.to_string(), "float operation resulted in an infinite value");
+    }
+
+    #[test]
+    fn unknown_token() {
+        let evaluators = DynEvaluators::<f64>::new();
+        match evaluators.parse::<_, DummyVariable, _>("3 4 +".split_whitespace()) {
+            Err(ExprResult::InvalidToken { position, evaluator, .. }) => {
+                assert_eq!(position, 2);
+                assert_eq!(evaluator, "+");
+            }
+            _ => panic!("expected an invalid token"),
+        }
+    }
+}
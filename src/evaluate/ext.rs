@@ -0,0 +1,35 @@
+use alloc::string::String;
+use evaluate::Evaluate;
+
+/// Extension methods available on every [`Evaluate`] implementation.
+///
+/// [`Evaluate`]: ../evaluate/trait.Evaluate.html
+pub trait EvaluateExt<T>: Evaluate<T> {
+    /// Describes the `Evaluator` with its name and its arity
+    /// (cf. `"+" (2 -> 1)`).
+    fn describe(&self) -> String {
+        format!("{:?} ({} -> {})", self.name(), self.operands_needed(), self.operands_generated())
+    }
+}
+
+impl<T, E: Evaluate<T> + ?Sized> EvaluateExt<T> for E {}
+
+#[cfg(all(test, feature = "float-evaluator", feature = "int-evaluator"))]
+mod tests {
+    use evaluate::{Evaluate, EvaluateExt, FloatEvaluator, IntEvaluator, StrictFloatEvaluator};
+
+    #[test]
+    fn evaluator_names() {
+        assert_eq!(Evaluate::<f32>::name(&FloatEvaluator::<f32>::Sqrt), "sqrt");
+        assert_eq!(Evaluate::<i32>::name(&IntEvaluator::<i32>::Add), "+");
+        let strict = StrictFloatEvaluator(FloatEvaluator::<f64>::Pow);
+        assert_eq!(strict.name(), "pow");
+    }
+
+    #[test]
+    fn describe_evaluator() {
+        assert_eq!(IntEvaluator::<i32>::Add.describe(), "\"+\" (2 -> 1)");
+        assert_eq!(IntEvaluator::<i32>::Swap.describe(), "\"swap\" (2 -> 2)");
+        assert_eq!(FloatEvaluator::<f32>::Zero.describe(), "\"zero\" (0 -> 1)");
+    }
+}
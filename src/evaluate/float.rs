@@ -1,15 +1,21 @@
-use std::marker::PhantomData;
-use std::fmt;
-use num::Float;
-use evaluate::Evaluate;
+use core::marker::PhantomData;
+use core::mem;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+use num_traits::{Float, NumCast};
+use evaluate::{Evaluate, with_ascii_lowercase};
+use expression::{Expression, ExprResult, EvalErr, ParseExprError};
+use variable::{DummyVariable, GetVariableOwned};
 use stack::Stack;
-use ::pop_two_operands;
+use {pop_two_operands, StackUnderflowError};
 use convert_ref::TryFromRef;
+use alloc::string::ToString;
 
 /// Basic Float Evaluator for any type that implement the [`Float`] Trait.
 ///
-/// [`Float`]: http://rust-num.github.io/num/num/trait.Float.html
-#[derive(Debug, Copy, Clone)]
+/// [`Float`]: https://docs.rs/num-traits/0.2/num_traits/float/trait.Float.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum FloatEvaluator<T: Float> {
     /// `"+"` will pop `2` operands and push `1`.
     Add,
@@ -35,10 +41,53 @@ pub enum FloatEvaluator<T: Float> {
     Swap,
     /// `"zero"` will pop `0` operand and push `1`.
     Zero,
-    /// `"zero"` will pop `0` operand and push `1`.
+    /// `"one"` will pop `0` operand and push `1`.
     One,
+    /// `"two"` will pop `0` operand and push `1`.
+    Two,
+    /// `"-one"` will pop `0` operand and push `1`.
+    NegOne,
+    /// `"half"` will pop `0` operand and push `1`.
+    Half,
     /// `"round"` will pop `1` operand and push `1`.
     Round,
+    /// `"sigmoid"` will pop `1` operand and push `1`.
+    Sigmoid,
+    /// `"relu"` will pop `1` operand and push `1`.
+    Relu,
+    /// `"lrelu"` will pop `2` operands (a slope and a value) and push `1`.
+    LeakyRelu,
+    /// `"copysign"` will pop `2` operands and push `1`, the magnitude
+    /// of the first operand with the sign of the second (cf. `3 -1 copysign` gives `-3`).
+    Copysign,
+    /// `"smoothstep"` will pop `1` operand and push `1`,
+    /// `t * t * (3 - 2 * t)` with `t` the operand clamped to `[0, 1]`.
+    Smoothstep,
+    /// `"smootherstep"` will pop `1` operand and push `1`,
+    /// `t * t * t * (t * (t * 6 - 15) + 10)` with `t` the operand clamped to `[0, 1]`.
+    Smootherstep,
+    /// `"smoothstep3"` will pop `3` operands (a value, a lower edge
+    /// and an upper edge) and push `1`, like the GLSL `smoothstep(edge0, edge1, x)`.
+    SmoothstepRange,
+    /// `"nextup"` will pop `1` operand and push `1`,
+    /// the smallest float greater than the operand.
+    Nextup,
+    /// `"nextdown"` will pop `1` operand and push `1`,
+    /// the largest float less than the operand.
+    Nextdown,
+    /// `"gamma"` will pop `1` operand and push `1`.
+    #[cfg(feature = "libm")]
+    Gamma,
+    /// `"lgamma"` will pop `1` operand and push `1`, the natural logarithm
+    /// of the absolute value of the gamma function.
+    #[cfg(feature = "libm")]
+    LnGamma,
+    /// `"erf"` will pop `1` operand and push `1`.
+    #[cfg(feature = "libm")]
+    Erf,
+    /// `"erfc"` will pop `1` operand and push `1`.
+    #[cfg(feature = "libm")]
+    Erfc,
     #[doc(hidden)]
     _Phantom(PhantomData<T>),
 }
@@ -46,7 +95,29 @@ pub enum FloatEvaluator<T: Float> {
 /// Type returned when an error occurs on float operation.
 #[derive(Debug, PartialEq)]
 pub enum FloatEvaluateErr {
-    // TODO add variants
+    NanResult,
+    InfiniteResult,
+    StackUnderflow,
+}
+
+impl fmt::Display for FloatEvaluateErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FloatEvaluateErr::NanResult => f.write_str("float operation resulted in NaN"),
+            FloatEvaluateErr::InfiniteResult => {
+                f.write_str("float operation resulted in an infinite value")
+            }
+            FloatEvaluateErr::StackUnderflow => StackUnderflowError.fmt(f),
+        }
+    }
+}
+
+impl Error for FloatEvaluateErr {}
+
+impl From<StackUnderflowError> for FloatEvaluateErr {
+    fn from(_: StackUnderflowError) -> Self {
+        FloatEvaluateErr::StackUnderflow
+    }
 }
 
 impl<T: Float> Evaluate<T> for FloatEvaluator<T> {
@@ -55,9 +126,13 @@ impl<T: Float> Evaluate<T> for FloatEvaluator<T> {
     fn operands_needed(&self) -> usize {
         use self::FloatEvaluator::*;
         match *self {
-            Add | Sub | Mul | Div | Pow | Rem | Swap => 2,
-            Neg | Sqrt | Log2 | Round | Exp => 1,
-            Zero | One => 0,
+            Add | Sub | Mul | Div | Pow | Rem | Swap | LeakyRelu | Copysign => 2,
+            Neg | Sqrt | Log2 | Round | Exp | Sigmoid | Relu => 1,
+            Smoothstep | Smootherstep | Nextup | Nextdown => 1,
+            SmoothstepRange => 3,
+            #[cfg(feature = "libm")]
+            Gamma | LnGamma | Erf | Erfc => 1,
+            Zero | One | Two | NegOne | Half => 0,
             _Phantom(_) => unreachable!(),
         }
     }
@@ -65,78 +140,365 @@ impl<T: Float> Evaluate<T> for FloatEvaluator<T> {
     fn operands_generated(&self) -> usize {
         use self::FloatEvaluator::*;
         match *self {
-            Add | Sub | Mul | Div | Rem | Neg | Sqrt | Pow | Log2 | Exp | Zero | One | Round => 1,
+            Add | Sub | Mul | Div | Rem | Neg | Sqrt | Pow | Log2 | Exp | Round => 1,
+            Sigmoid | Relu | LeakyRelu | Copysign => 1,
+            Smoothstep | Smootherstep | SmoothstepRange => 1,
+            Nextup | Nextdown => 1,
+            #[cfg(feature = "libm")]
+            Gamma | LnGamma | Erf | Erfc => 1,
+            Zero | One | Two | NegOne | Half => 1,
             Swap => 2,
             _Phantom(_) => unreachable!(),
         }
     }
 
+    fn name(&self) -> &'static str {
+        use self::FloatEvaluator::*;
+        match *self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            Neg => "neg",
+            Sqrt => "sqrt",
+            Pow => "pow",
+            Log2 => "log2",
+            Exp => "exp",
+            Swap => "swap",
+            Zero => "zero",
+            One => "one",
+            Two => "two",
+            NegOne => "-one",
+            Half => "half",
+            Round => "round",
+            Sigmoid => "sigmoid",
+            Relu => "relu",
+            LeakyRelu => "lrelu",
+            Copysign => "copysign",
+            Smoothstep => "smoothstep",
+            Smootherstep => "smootherstep",
+            SmoothstepRange => "smoothstep3",
+            Nextup => "nextup",
+            Nextdown => "nextdown",
+            #[cfg(feature = "libm")]
+            Gamma => "gamma",
+            #[cfg(feature = "libm")]
+            LnGamma => "lgamma",
+            #[cfg(feature = "libm")]
+            Erf => "erf",
+            #[cfg(feature = "libm")]
+            Erfc => "erfc",
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    #[allow(clippy::unit_arg)]
     fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
         use self::FloatEvaluator::*;
         match self {
             Add => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 Ok(stack.push(a + b))
             }
             Sub => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 Ok(stack.push(a - b))
             }
             Mul => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 Ok(stack.push(a * b))
             }
             Div => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 Ok(stack.push(a / b))
             }
             Rem => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 Ok(stack.push(a % b))
             }
             Neg => {
-                let a = stack.pop().unwrap();
+                let a = stack.pop().ok_or(StackUnderflowError)?;
                 Ok(stack.push(-a))
             }
             Sqrt => {
-                let a = stack.pop().unwrap();
+                let a = stack.pop().ok_or(StackUnderflowError)?;
                 Ok(stack.push(a.sqrt()))
             }
             Pow => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 Ok(stack.push(a.powf(b)))
             }
             Log2 => {
-                let a = stack.pop().unwrap();
+                let a = stack.pop().ok_or(StackUnderflowError)?;
                 Ok(stack.push(a.log2()))
             }
             Exp => {
-                let a = stack.pop().unwrap();
+                let a = stack.pop().ok_or(StackUnderflowError)?;
                 Ok(stack.push(a.exp()))
             }
             Swap => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 stack.push(b);
                 stack.push(a);
                 Ok(())
             }
             Zero => Ok(stack.push(T::zero())),
             One => Ok(stack.push(T::one())),
+            Two => Ok(stack.push(T::one() + T::one())),
+            NegOne => Ok(stack.push(-T::one())),
+            Half => Ok(stack.push(T::one() / (T::one() + T::one()))),
             Round => {
-                let a = stack.pop().unwrap();
+                let a = stack.pop().ok_or(StackUnderflowError)?;
                 Ok(stack.push(a.round()))
             }
+            Sigmoid => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(T::one() / (T::one() + (-a).exp())))
+            }
+            Relu => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(a.max(T::zero())))
+            }
+            LeakyRelu => {
+                let (slope, a) = pop_two_operands(stack)?;
+                Ok(stack.push(if a >= T::zero() { a } else { slope * a }))
+            }
+            Copysign => {
+                let (magnitude, sign) = pop_two_operands(stack)?;
+                Ok(stack.push(magnitude.copysign(sign)))
+            }
+            Smoothstep => {
+                let t = clamp_unit(stack.pop().ok_or(StackUnderflowError)?);
+                Ok(stack.push(t * t * (constant::<T>(3.0) - constant::<T>(2.0) * t)))
+            }
+            Smootherstep => {
+                let t = clamp_unit(stack.pop().ok_or(StackUnderflowError)?);
+                let (six, fifteen, ten) = (constant::<T>(6.0), constant(15.0), constant(10.0));
+                Ok(stack.push(t * t * t * (t * (t * six - fifteen) + ten)))
+            }
+            SmoothstepRange => {
+                let (edge0, edge1) = pop_two_operands(stack)?;
+                let x = stack.pop().ok_or(StackUnderflowError)?;
+                let t = clamp_unit((x - edge0) / (edge1 - edge0));
+                Ok(stack.push(t * t * (constant::<T>(3.0) - constant::<T>(2.0) * t)))
+            }
+            Nextup => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(next_float(a, f32::next_up, f64::next_up)))
+            }
+            Nextdown => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(next_float(a, f32::next_down, f64::next_down)))
+            }
+            #[cfg(feature = "libm")]
+            Gamma => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(apply_f64(a, libm::tgamma)))
+            }
+            #[cfg(feature = "libm")]
+            LnGamma => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(apply_f64(a, libm::lgamma)))
+            }
+            #[cfg(feature = "libm")]
+            Erf => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(apply_f64(a, libm::erf)))
+            }
+            #[cfg(feature = "libm")]
+            Erfc => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(apply_f64(a, libm::erfc)))
+            }
             _Phantom(_) => unreachable!(),
         }
     }
 }
 
+/// Computes a `libm` function, which only works on `f64`, for any `Float`.
+#[cfg(feature = "libm")]
+fn apply_f64<T: Float>(value: T, f: fn(f64) -> f64) -> T {
+    value.to_f64().and_then(|value| <T as NumCast>::from(f(value))).unwrap_or_else(T::nan)
+}
+
+/// Computes the neighbour of a `Float`, on its `f32` representation if it has the size
+/// of an `f32` and on its `f64` representation otherwise. `Float` types other than `f32`
+/// and `f64` are converted to and from `f64`, their neighbour is the rounded `f64` one.
+fn next_float<T: Float>(value: T, next_f32: fn(f32) -> f32, next_f64: fn(f64) -> f64) -> T {
+    let next = if mem::size_of::<T>() == mem::size_of::<f32>() {
+        value.to_f32().and_then(|value| <T as NumCast>::from(next_f32(value)))
+    } else {
+        value.to_f64().and_then(|value| <T as NumCast>::from(next_f64(value)))
+    };
+    next.unwrap_or_else(T::nan)
+}
+
+fn constant<T: Float>(value: f64) -> T {
+    <T as NumCast>::from(value).unwrap()
+}
+
+fn clamp_unit<T: Float>(value: T) -> T {
+    value.max(T::zero()).min(T::one())
+}
+
+fn check_float<T: Float>(value: T) -> Result<T, FloatEvaluateErr> {
+    if value.is_nan() {
+        Err(FloatEvaluateErr::NanResult)
+    } else if value.is_infinite() {
+        Err(FloatEvaluateErr::InfiniteResult)
+    } else {
+        Ok(value)
+    }
+}
+
+impl<T: Float, V: Clone> Expression<T, V, FloatEvaluator<T>> {
+    /// Evaluate `RPN` expressions like [`evaluate()`] but returns an error
+    /// if the result is `NaN` or infinite.
+    ///
+    /// [`evaluate()`]: ../expression/struct.Expression.html#method.evaluate
+    pub fn evaluate_checked(&self) -> Result<T, FloatEvaluateErr>
+        where (): From<V>
+    {
+        self.evaluate().and_then(check_float)
+    }
+
+    /// Evaluate `RPN` expressions containing variables like [`evaluate_with_variables()`]
+    /// but returns an error if the result is `NaN` or infinite.
+    ///
+    /// [`evaluate_with_variables()`]: ../expression/struct.Expression.html#method.evaluate_with_variables
+    pub fn evaluate_checked_with_variables<I, M, C>(&self, variables: &C)
+                                                    -> Result<T, EvalErr<V, FloatEvaluateErr>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        self.evaluate_with_variables(variables)
+            .and_then(|value| check_float(value).map_err(EvalErr::EvalError))
+    }
+}
+
+/// Float Evaluator that wraps a [`FloatEvaluator`] and returns an error
+/// when an operation generates a `NaN` or an infinite value.
+///
+/// [`FloatEvaluator`]: enum.FloatEvaluator.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct StrictFloatEvaluator<T: Float>(pub FloatEvaluator<T>);
+
+impl<T: Float> Evaluate<T> for StrictFloatEvaluator<T> {
+    type Err = FloatEvaluateErr;
+
+    fn operands_needed(&self) -> usize {
+        self.0.operands_needed()
+    }
+
+    fn operands_generated(&self) -> usize {
+        self.0.operands_generated()
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
+        let generated = self.0.operands_generated();
+        self.0.evaluate(stack)?;
+        let values = stack.as_slice();
+        for &value in &values[values.len() - generated..] {
+            check_float(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Float> TryFromRef<&'a str> for StrictFloatEvaluator<T> {
+    type Err = FloatErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        FloatEvaluator::try_from_ref(expr).map(StrictFloatEvaluator)
+    }
+}
+
+impl<T: Float> fmt::Display for StrictFloatEvaluator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Float Evaluator that wraps a [`FloatEvaluator`] and accepts
+/// operators regardless of their case (cf. `"SQRT"` or `"Neg"`).
+///
+/// [`FloatEvaluator`]: enum.FloatEvaluator.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CaseInsensitiveFloatEvaluator<T: Float>(pub FloatEvaluator<T>);
+
+delegate_evaluate!(CaseInsensitiveFloatEvaluator(FloatEvaluator) where T: Float);
+
+impl<'a, T: Float> TryFromRef<&'a str> for CaseInsensitiveFloatEvaluator<T> {
+    type Err = FloatErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        with_ascii_lowercase(expr, |lowercase| FloatEvaluator::try_from_ref(&lowercase).ok())
+            .flatten()
+            .map(CaseInsensitiveFloatEvaluator)
+            .ok_or(FloatErr::InvalidExpr(expr))
+    }
+}
+
 /// Type returned when a conversion cannot be performed.
 #[derive(Debug)]
 pub enum FloatErr<'a> { // TODO change name
     InvalidExpr(&'a str),
 }
 
+impl<'a> fmt::Display for FloatErr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FloatErr::InvalidExpr(expr) => write!(f, "unknown float operator: '{}'", expr),
+        }
+    }
+}
+
+/// Float Evaluator that wraps a [`FloatEvaluator`] and additionally recognizes
+/// some common aliases of its operators (cf. `"^"` or `"**"` for `"pow"`
+/// and `"mod"` for `"%"`), the canonical names are used when displayed.
+///
+/// [`FloatEvaluator`]: enum.FloatEvaluator.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AliasedFloatEvaluator<T: Float>(pub FloatEvaluator<T>);
+
+impl<T: Float> FloatEvaluator<T> {
+    /// Wraps this evaluator into an [`AliasedFloatEvaluator`].
+    ///
+    /// [`AliasedFloatEvaluator`]: struct.AliasedFloatEvaluator.html
+    pub fn with_aliases(self) -> AliasedFloatEvaluator<T> {
+        AliasedFloatEvaluator(self)
+    }
+}
+
+impl<T: Float> AliasedFloatEvaluator<T> {
+    fn from_alias(expr: &str) -> Option<FloatEvaluator<T>> {
+        match expr {
+            "^" | "**" => Some(FloatEvaluator::Pow),
+            "mod" => Some(FloatEvaluator::Rem),
+            _ => None,
+        }
+    }
+}
+
+delegate_evaluate!(AliasedFloatEvaluator(FloatEvaluator) where T: Float);
+
+impl<'a, T: Float> TryFromRef<&'a str> for AliasedFloatEvaluator<T> {
+    type Err = FloatErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        match Self::from_alias(expr) {
+            Some(evaluator) => Ok(evaluator.with_aliases()),
+            None => FloatEvaluator::try_from_ref(expr).map(AliasedFloatEvaluator),
+        }
+    }
+}
+
+impl<'a> Error for FloatErr<'a> {}
+
+implement_arithmetic_operators!(FloatEvaluator where T: Float);
+
 impl<'a, T: Float> TryFromRef<&'a str> for FloatEvaluator<T> {
     type Err = FloatErr<'a>;
     fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
@@ -155,7 +517,27 @@ impl<'a, T: Float> TryFromRef<&'a str> for FloatEvaluator<T> {
             "swap" => Ok(Swap),
             "zero" => Ok(Zero),
             "one" => Ok(One),
+            "two" => Ok(Two),
+            "-one" => Ok(NegOne),
+            "half" => Ok(Half),
             "round" => Ok(Round),
+            "sigmoid" => Ok(Sigmoid),
+            "relu" => Ok(Relu),
+            "lrelu" => Ok(LeakyRelu),
+            "copysign" => Ok(Copysign),
+            "smoothstep" => Ok(Smoothstep),
+            "smootherstep" => Ok(Smootherstep),
+            "smoothstep3" => Ok(SmoothstepRange),
+            "nextup" => Ok(Nextup),
+            "nextdown" => Ok(Nextdown),
+            #[cfg(feature = "libm")]
+            "gamma" => Ok(Gamma),
+            #[cfg(feature = "libm")]
+            "lgamma" => Ok(LnGamma),
+            #[cfg(feature = "libm")]
+            "erf" => Ok(Erf),
+            #[cfg(feature = "libm")]
+            "erfc" => Ok(Erfc),
             _ => Err(FloatErr::InvalidExpr(expr)),
         }
     }
@@ -163,34 +545,109 @@ impl<'a, T: Float> TryFromRef<&'a str> for FloatEvaluator<T> {
 
 impl<T: Float> fmt::Display for FloatEvaluator<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::FloatEvaluator::*;
-        let name = match *self {
-            Add => "+",
-            Sub => "-",
-            Mul => "*",
-            Div => "/",
-            Rem => "%",
-            Neg => "neg",
-            Sqrt => "sqrt",
-            Pow => "pow",
-            Log2 => "log2",
-            Exp => "exp",
-            Swap => "swap",
-            Zero => "zero",
-            One => "one",
-            Round => "round",
-            _Phantom(_) => unreachable!(),
-        };
-        f.write_str(name)
+        f.write_str(Evaluate::<T>::name(self))
+    }
+}
+
+impl<T: Float> FromStr for Expression<T, DummyVariable, FloatEvaluator<T>>
+    where for<'a> T: TryFromRef<&'a str>
+{
+    type Err = ParseExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::from_iter(s.split_whitespace()).map_err(|err| {
+            match err {
+                ExprResult::OperandErr(err) => ParseExprError::OperandErr(err),
+                ExprResult::InvalidToken { evaluator: FloatErr::InvalidExpr(token), .. } => {
+                    ParseExprError::InvalidToken(token.to_string())
+                }
+            }
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use expression::{ExprResult, OperandErr};
+    use expression::{EvalErr, ExprResult, OperandErr, ParseExprError};
     use evaluate::{FloatErr, FloatExpr, VariableFloatExpr};
     use variable::IndexVar;
 
+    #[cfg(feature = "libm")]
+    #[test]
+    fn libm_functions() {
+        let expr = FloatExpr::<f64>::from_iter("1 gamma".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1.0));
+
+        let expr = FloatExpr::<f64>::from_iter("5 gamma".split_whitespace()).unwrap();
+        assert!((expr.evaluate().unwrap() - 24.0).abs() < 1e-9);
+
+        let expr = FloatExpr::<f64>::from_iter("1 lgamma".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0.0));
+
+        let expr = FloatExpr::<f32>::from_iter("0.5 erf".split_whitespace()).unwrap();
+        assert!((expr.evaluate().unwrap() - 0.5205).abs() < 1e-4);
+
+        let expr = FloatExpr::<f64>::from_iter("0.5 erfc".split_whitespace()).unwrap();
+        assert!((expr.evaluate().unwrap() - 0.4795).abs() < 1e-4);
+    }
+
+    #[test]
+    fn simple_copysign() {
+        let expr = FloatExpr::<f64>::from_iter("3.0 -1.0 copysign".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(-3.0));
+
+        let expr = FloatExpr::<f64>::from_iter("-5.0 1.0 copysign".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(5.0));
+    }
+
+    #[test]
+    fn smoothstep_functions() {
+        let parse = |expr: &str| FloatExpr::<f64>::from_iter(expr.split_whitespace()).unwrap();
+        assert_eq!(parse("0.5 smoothstep").evaluate(), Ok(0.5));
+        assert_eq!(parse("0.0 smoothstep").evaluate(), Ok(0.0));
+        assert_eq!(parse("1.0 smoothstep").evaluate(), Ok(1.0));
+        assert_eq!(parse("-2 smoothstep").evaluate(), Ok(0.0));
+        assert_eq!(parse("0.25 smoothstep").evaluate(), Ok(0.15625));
+
+        assert_eq!(parse("0.5 smootherstep").evaluate(), Ok(0.5));
+        assert_eq!(parse("3 smootherstep").evaluate(), Ok(1.0));
+        assert_eq!(parse("0.25 smootherstep").evaluate(), Ok(0.103515625));
+
+        assert_eq!(parse("0.5 0 1 smoothstep3").evaluate(), Ok(0.5));
+        assert_eq!(parse("15 10 20 smoothstep3").evaluate(), Ok(0.5));
+        assert_eq!(parse("25 10 20 smoothstep3").evaluate(), Ok(1.0));
+    }
+
+    #[test]
+    fn next_representable_floats() {
+        let parse = |expr: &str| FloatExpr::<f32>::from_iter(expr.split_whitespace()).unwrap();
+        assert_eq!(parse("0 nextup").evaluate(), Ok(f32::from_bits(1)));
+        assert_eq!(parse("1.0 nextdown").evaluate(), Ok(1.0 - f32::EPSILON / 2.0));
+        assert_eq!(parse("1.0 nextup").evaluate(), Ok(1.0 + f32::EPSILON));
+        assert_eq!(parse("0 nextdown").evaluate(), Ok(-f32::from_bits(1)));
+
+        let expr = FloatExpr::<f64>::from_iter("1.0 nextup".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1.0 + f64::EPSILON));
+    }
+
+    #[test]
+    fn activation_functions() {
+        let expr = FloatExpr::<f32>::from_iter("0 sigmoid".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0.5));
+
+        let expr = FloatExpr::<f32>::from_iter("-1 relu".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0.0));
+
+        let expr = FloatExpr::<f32>::from_iter("2 relu".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(2.0));
+
+        let expr = FloatExpr::<f64>::from_iter("0.01 -3 lrelu".split_whitespace()).unwrap();
+        assert!((expr.evaluate().unwrap() + 0.03).abs() < 1e-12);
+
+        let expr = FloatExpr::<f64>::from_iter("0.01 3 lrelu".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(3.0));
+    }
+
     #[test]
     fn bad_operator() {
         let expr = "3 4 + &";
@@ -198,7 +655,18 @@ mod tests {
         let res = FloatExpr::<f32>::from_iter(tokens);
         match res {
             Err(ExprResult::InvalidToken { evaluator: FloatErr::InvalidExpr("&"), .. }) => (),
-            _ => panic!(res),
+            _ => panic!("{:?}", res),
+        }
+    }
+
+    #[test]
+    fn bad_operator_position() {
+        let expr = "3 4 + & 2";
+        let tokens = expr.split_whitespace();
+        let res = FloatExpr::<f32>::from_iter(tokens);
+        match res {
+            Err(ExprResult::InvalidToken { position: 3, evaluator: FloatErr::InvalidExpr("&"), .. }) => (),
+            _ => panic!("{:?}", res),
         }
     }
 
@@ -208,8 +676,8 @@ mod tests {
         let tokens = expr.split_whitespace();
         let res = FloatExpr::<f32>::from_iter(tokens);
         match res {
-            Err(ExprResult::OperandErr(OperandErr::TooManyOperands)) => (),
-            _ => panic!(res),
+            Err(ExprResult::OperandErr(OperandErr::TooManyOperands { found: 2 })) => (),
+            _ => panic!("{:?}", res),
         }
     }
 
@@ -219,8 +687,8 @@ mod tests {
         let tokens = expr.split_whitespace();
         let res = FloatExpr::<f32>::from_iter(tokens);
         match res {
-            Err(ExprResult::OperandErr(OperandErr::NotEnoughOperand)) => (),
-            _ => panic!(res),
+            Err(ExprResult::OperandErr(OperandErr::NotEnoughOperand { needed: 2, available: 1 })) => (),
+            _ => panic!("{:?}", res),
         }
     }
 
@@ -240,6 +708,21 @@ mod tests {
         assert_eq!(expr.evaluate(), Ok(1.0));
     }
 
+    #[test]
+    fn simple_two_and_neg_one() {
+        let expr = FloatExpr::<f32>::from_iter("two two *".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(4.0));
+
+        let expr = FloatExpr::<f32>::from_iter("-one neg".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1.0));
+    }
+
+    #[test]
+    fn simple_half() {
+        let expr = FloatExpr::<f32>::from_iter("one half *".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0.5));
+    }
+
     #[test]
     fn simple_multiplication() {
         let expr_str = "3 4 *";
@@ -382,6 +865,7 @@ mod tests {
         assert_eq!(expr.evaluate_with_variables(&variables), Ok(4.0));
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn simple_hashmap_variable_expression() {
         use std::collections::HashMap;
@@ -437,4 +921,182 @@ mod tests {
         let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
         assert_eq!(expr.evaluate_with_variables(&variables), Ok(500.0));
     }
+
+    #[test]
+    fn parse_from_str() {
+        let expr = "3 4 +".parse::<FloatExpr<f32>>().unwrap();
+        assert_eq!(expr.evaluate(), Ok(7.0));
+
+        let res = "3 &".parse::<FloatExpr<f32>>();
+        assert_eq!(res, Err(ParseExprError::InvalidToken("&".to_string())));
+
+        let res = "3 4".parse::<FloatExpr<f32>>();
+        let err = OperandErr::TooManyOperands { found: 2 };
+        assert_eq!(res, Err(ParseExprError::OperandErr(err)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn simple_named_variable_expression() {
+        use std::collections::HashMap;
+        use evaluate::VariableFloatExprNamed;
+
+        let mut variables = HashMap::new();
+        variables.insert("width".to_string(), 3.0);
+        variables.insert("height".to_string(), 4.0);
+
+        let expr_str = "$width $height *";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExprNamed::<f32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_with_variables(&variables), Ok(12.0));
+        assert_eq!(&expr.to_string(), expr_str);
+    }
+
+    #[test]
+    fn simple_slice_variable_expression() {
+        let variables = [3.0, 500.0];
+
+        let expr_str = "3 $1 + $0 -";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_with_variables(&variables), Ok(500.0));
+        assert_eq!(expr.evaluate_with_variables(&variables[..]), Ok(500.0));
+    }
+
+    #[test]
+    fn simple_closure_variable_expression() {
+        let variables = |idx: usize| if idx == 0 { Some(42.0) } else { None };
+
+        let expr_str = "3 $0 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_with_variables(&variables), Ok(45.0));
+
+        let expr_str = "3 $1 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        assert!(expr.evaluate_with_variables(&variables).is_err());
+    }
+
+    #[test]
+    fn stack_underflow() {
+        use evaluate::{Evaluate, FloatEvaluateErr, FloatEvaluator};
+        use stack::Stack;
+        use {pop_two_operands, try_pop_two_operands, StackUnderflowError};
+
+        let mut stack = Stack::new();
+        stack.push(3.0);
+        assert_eq!(pop_two_operands(&mut stack), Err(StackUnderflowError));
+        assert_eq!(try_pop_two_operands(&mut stack), None);
+
+        let res = FloatEvaluator::Add.evaluate(&mut stack);
+        assert_eq!(res, Err(FloatEvaluateErr::StackUnderflow));
+        assert_eq!(res.unwrap_err().to_string(), "not enough operands on the stack");
+
+        let mut stack = Stack::<f64>::new();
+        let res = FloatEvaluator::Nextup.evaluate(&mut stack);
+        assert_eq!(res, Err(FloatEvaluateErr::StackUnderflow));
+        stack.push(0.0);
+        stack.push(1.0);
+        let res = FloatEvaluator::SmoothstepRange.evaluate(&mut stack);
+        assert_eq!(res, Err(FloatEvaluateErr::StackUnderflow));
+    }
+
+    #[test]
+    fn display_errors() {
+        let err = FloatExpr::<f32>::from_iter("3 ^".split_whitespace()).unwrap_err();
+        assert!(format!("{}", err).contains("unknown float operator: '^'"));
+
+        let expr_str = "3 $2 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let err = expr.evaluate_with_variables(&vec![1.0]).unwrap_err();
+        assert_eq!(format!("{}", err), "variable not found: $2");
+    }
+
+    #[test]
+    fn case_insensitive_operators() {
+        use evaluate::CaseInsensitiveFloatExpr;
+
+        let expr = CaseInsensitiveFloatExpr::<f32>::from_iter("9 SQRT".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(3.0));
+        assert_eq!(expr.to_string(), "9 sqrt");
+
+        let expr = CaseInsensitiveFloatExpr::<f32>::from_iter("3 NEG".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(-3.0));
+
+        let res = CaseInsensitiveFloatExpr::<f32>::from_iter("3 NOPE".split_whitespace());
+        match res {
+            Err(ExprResult::InvalidToken { evaluator: FloatErr::InvalidExpr("NOPE"), .. }) => (),
+            _ => panic!("{:?}", res),
+        }
+
+        let res = CaseInsensitiveFloatExpr::<f32>::from_iter("3 SMOOTHERSTEP_TOO_LONG".split_whitespace());
+        match res {
+            Err(ExprResult::InvalidToken {
+                evaluator: FloatErr::InvalidExpr("SMOOTHERSTEP_TOO_LONG"), ..
+            }) => (),
+            _ => panic!("{:?}", res),
+        }
+    }
+
+    #[test]
+    fn aliased_operators() {
+        use evaluate::AliasedFloatExpr;
+
+        let expr = AliasedFloatExpr::<f32>::from_iter("3 4 ^".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(81.0));
+        assert_eq!(expr.to_string(), "3 4 pow");
+
+        let expr = AliasedFloatExpr::<f32>::from_iter("3 4 ** 5 mod".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1.0));
+    }
+
+    #[test]
+    fn strict_nan() {
+        use evaluate::{FloatEvaluateErr, StrictFloatExpr};
+
+        let expr_str = "0 0 / sqrt";
+        let tokens = expr_str.split_whitespace();
+        let expr = StrictFloatExpr::<f32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(FloatEvaluateErr::NanResult));
+
+        let tokens = expr_str.split_whitespace();
+        let expr = FloatExpr::<f32>::from_iter(tokens).unwrap();
+        assert!(expr.evaluate().unwrap().is_nan());
+    }
+
+    #[test]
+    fn strict_infinite() {
+        use evaluate::{FloatEvaluateErr, StrictFloatExpr};
+
+        let expr_str = "9 0 / 3 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = StrictFloatExpr::<f32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(FloatEvaluateErr::InfiniteResult));
+        assert_eq!(&expr.to_string(), expr_str);
+
+        let tokens = "9 2 / 3 +".split_whitespace();
+        let expr = StrictFloatExpr::<f32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(7.5));
+    }
+
+    #[test]
+    fn evaluate_checked() {
+        use evaluate::FloatEvaluateErr;
+
+        let expr = FloatExpr::<f32>::from_iter("9 0 /".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate_checked(), Err(FloatEvaluateErr::InfiniteResult));
+
+        let expr = FloatExpr::<f32>::from_iter("0 0 /".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate_checked(), Err(FloatEvaluateErr::NanResult));
+
+        let expr = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate_checked(), Ok(7.0));
+
+        let tokens = "$0 0 /".split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let res = expr.evaluate_checked_with_variables(&vec![1.0]);
+        assert_eq!(res, Err(EvalErr::EvalError(FloatEvaluateErr::InfiniteResult)));
+    }
 }
@@ -1,16 +1,21 @@
-use std::marker::PhantomData;
-use std::fmt;
-use num::{PrimInt, Signed, checked_pow};
-use evaluate::Evaluate;
+use core::marker::PhantomData;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+use num_traits::{NumCast, PrimInt, Signed, checked_pow};
+use evaluate::{Evaluate, with_ascii_lowercase};
+use expression::{Expression, ExprResult, ParseExprError};
+use variable::DummyVariable;
 use stack::Stack;
-use ::pop_two_operands;
+use {pop_two_operands, StackUnderflowError};
 use convert_ref::TryFromRef;
+use alloc::string::ToString;
 
 /// Basic Signed Integer Evaluator for any type that implement [`PrimInt`] and [`Signed`] Traits.
 ///
-/// [`PrimInt`]: http://rust-num.github.io/num/num/trait.PrimInt.html
-/// [`Signed`]: http://rust-num.github.io/num/num/trait.Signed.html
-#[derive(Debug, Copy, Clone)]
+/// [`PrimInt`]: https://docs.rs/num-traits/0.2/num_traits/int/trait.PrimInt.html
+/// [`Signed`]: https://docs.rs/num-traits/0.2/num_traits/sign/trait.Signed.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum IntEvaluator<T: PrimInt + Signed> {
     /// `"+"` will pop `2` operands and push `1`.
     Add,
@@ -26,17 +31,38 @@ pub enum IntEvaluator<T: PrimInt + Signed> {
     Neg,
     /// `"pow"` will pop `2` operands and push `1`.
     Pow,
+    /// `"mid"` will pop `2` operands and push `1`, never overflows.
+    Midpoint,
+    /// `"absdiff"` will pop `2` operands and push `1`, the absolute difference
+    /// of the operands.
+    AbsDiff,
+    /// `"next_pow2"` will pop `1` operand and push `1`, the smallest power of two
+    /// greater than or equal to the operand (`1` for operands less than or equal to `1`).
+    NextPow2,
+    /// `"is_pow2"` will pop `1` operand and push `1`, `1` if the operand
+    /// is a power of two, `0` otherwise.
+    IsPow2,
+    /// `"ilog2"` will pop `1` operand and push `1`, the base 2 logarithm
+    /// of the operand rounded down.
+    ILog2,
+    /// `"ilog10"` will pop `1` operand and push `1`, the base 10 logarithm
+    /// of the operand rounded down.
+    ILog10,
     /// `"swap"` will pop `2` operands and push `2`.
     Swap,
     /// `"zero"` will pop `0` operand and push `1`.
     Zero,
-    /// `"zero"` will pop `0` operand and push `1`.
+    /// `"one"` will pop `0` operand and push `1`.
     One,
+    /// `"two"` will pop `0` operand and push `1`.
+    Two,
+    /// `"-one"` will pop `0` operand and push `1`.
+    NegOne,
     #[doc(hidden)]
     _Phantom(PhantomData<T>),
 }
 
-/// Type returned when an error occurs on signed integer operation.
+/// Type returned when an error occurs on signed or unsigned integer operation.
 #[derive(Debug, PartialEq)]
 pub enum IntEvaluateErr<T> {
     ConvertToU32(T),
@@ -46,6 +72,37 @@ pub enum IntEvaluateErr<T> {
     PowOverflow(T, usize),
     InvalidDiv(T, T),
     InvalidRem(T, T),
+    NextPow2Overflow(T),
+    InvalidLog(T),
+    AbsDiffOverflow(T, T),
+    StackUnderflow,
+}
+
+impl<T: fmt::Display> fmt::Display for IntEvaluateErr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::IntEvaluateErr::*;
+        match *self {
+            ConvertToU32(ref a) => write!(f, "cannot convert {} to an unsigned exponent", a),
+            AddOverflow(ref a, ref b) => write!(f, "integer overflow: {} + {}", a, b),
+            SubUnderflow(ref a, ref b) => write!(f, "integer underflow: {} - {}", a, b),
+            MulOverflow(ref a, ref b) => write!(f, "integer overflow: {} * {}", a, b),
+            PowOverflow(ref a, ref b) => write!(f, "integer overflow: {} pow {}", a, b),
+            InvalidDiv(ref a, ref b) => write!(f, "invalid division: {} / {}", a, b),
+            InvalidRem(ref a, ref b) => write!(f, "invalid remainder: {} % {}", a, b),
+            NextPow2Overflow(ref a) => write!(f, "integer overflow: {} next_pow2", a),
+            InvalidLog(ref a) => write!(f, "logarithm of a non positive number: {}", a),
+            AbsDiffOverflow(ref a, ref b) => write!(f, "integer overflow: {} absdiff {}", a, b),
+            StackUnderflow => StackUnderflowError.fmt(f),
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> Error for IntEvaluateErr<T> {}
+
+impl<T> From<StackUnderflowError> for IntEvaluateErr<T> {
+    fn from(_: StackUnderflowError) -> Self {
+        IntEvaluateErr::StackUnderflow
+    }
 }
 
 impl<T: PrimInt + Signed> Evaluate<T> for IntEvaluator<T> {
@@ -54,9 +111,9 @@ impl<T: PrimInt + Signed> Evaluate<T> for IntEvaluator<T> {
     fn operands_needed(&self) -> usize {
         use self::IntEvaluator::*;
         match *self {
-            Add | Sub | Mul | Div | Pow | Rem | Swap => 2,
-            Neg => 1,
-            Zero | One => 0,
+            Add | Sub | Mul | Div | Pow | Rem | Midpoint | AbsDiff | Swap => 2,
+            Neg | NextPow2 | IsPow2 | ILog2 | ILog10 => 1,
+            Zero | One | Two | NegOne => 0,
             _Phantom(_) => unreachable!(),
         }
     }
@@ -64,38 +121,66 @@ impl<T: PrimInt + Signed> Evaluate<T> for IntEvaluator<T> {
     fn operands_generated(&self) -> usize {
         use self::IntEvaluator::*;
         match *self {
-            Add | Sub | Mul | Div | Rem | Neg | Pow | Zero | One => 1,
+            Add | Sub | Mul | Div | Rem | Neg | Pow | Midpoint | AbsDiff => 1,
+            NextPow2 | IsPow2 | ILog2 | ILog10 => 1,
+            Zero | One | Two | NegOne => 1,
             Swap => 2,
             _Phantom(_) => unreachable!(),
         }
     }
 
+    fn name(&self) -> &'static str {
+        use self::IntEvaluator::*;
+        match *self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            Neg => "neg",
+            Pow => "pow",
+            Midpoint => "mid",
+            AbsDiff => "absdiff",
+            NextPow2 => "next_pow2",
+            IsPow2 => "is_pow2",
+            ILog2 => "ilog2",
+            ILog10 => "ilog10",
+            Swap => "swap",
+            Zero => "zero",
+            One => "one",
+            Two => "two",
+            NegOne => "-one",
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    #[allow(clippy::unit_arg)]
     fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
         use self::IntEvaluator::*;
         use self::IntEvaluateErr::*;
         match self {
             Add => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 let c = a.checked_add(&b).ok_or(AddOverflow(a, b))?;
                 Ok(stack.push(c))
             }
             Sub => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 let c = a.checked_sub(&b).ok_or(SubUnderflow(a, b))?;
                 Ok(stack.push(c))
             }
             Mul => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 let c = a.checked_mul(&b).ok_or(MulOverflow(a, b))?;
                 Ok(stack.push(c))
             }
             Div => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 let c = a.checked_div(&b).ok_or(InvalidDiv(a, b))?;
                 Ok(stack.push(c))
             }
             Rem => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 if b == T::zero() {
                     Err(InvalidRem(a, b))
                 } else {
@@ -103,23 +188,76 @@ impl<T: PrimInt + Signed> Evaluate<T> for IntEvaluator<T> {
                 }
             }
             Neg => {
-                let a = stack.pop().unwrap();
+                let a = stack.pop().ok_or(StackUnderflowError)?;
                 Ok(stack.push(-a))
             }
             Pow => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 let b = b.to_usize().ok_or(ConvertToU32(b))?;
                 let pow = checked_pow(a, b).ok_or(PowOverflow(a, b))?;
                 Ok(stack.push(pow))
             }
+            Midpoint => {
+                let (a, b) = pop_two_operands(stack)?;
+                let two = T::one() + T::one();
+                Ok(stack.push(a / two + b / two + (a % two + b % two) / two))
+            }
+            AbsDiff => {
+                let (a, b) = pop_two_operands(stack)?;
+                let diff = if a >= b { a.checked_sub(&b) } else { b.checked_sub(&a) };
+                let diff = diff.ok_or(AbsDiffOverflow(a, b))?;
+                Ok(stack.push(diff))
+            }
+            NextPow2 => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                if a <= T::one() {
+                    return Ok(stack.push(T::one()))
+                }
+                // the sign bit cannot hold a power of two
+                let bits = T::zero().count_zeros();
+                let shift = bits - (a - T::one()).leading_zeros();
+                if shift >= bits - 1 {
+                    return Err(NextPow2Overflow(a))
+                }
+                Ok(stack.push(T::one() << shift as usize))
+            }
+            IsPow2 => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                let is_pow2 = a > T::zero() && a.count_ones() == 1;
+                Ok(stack.push(if is_pow2 { T::one() } else { T::zero() }))
+            }
+            ILog2 => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                if a <= T::zero() {
+                    return Err(InvalidLog(a))
+                }
+                let bits = T::zero().count_zeros();
+                let log = bits - 1 - a.leading_zeros();
+                Ok(stack.push(<T as NumCast>::from(log).unwrap()))
+            }
+            ILog10 => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                if a <= T::zero() {
+                    return Err(InvalidLog(a))
+                }
+                let ten = <T as NumCast>::from(10).unwrap();
+                let (mut rest, mut log) = (a, T::zero());
+                while rest >= ten {
+                    rest = rest / ten;
+                    log = log + T::one();
+                }
+                Ok(stack.push(log))
+            }
             Swap => {
-                let (a, b) = pop_two_operands(stack).unwrap();
+                let (a, b) = pop_two_operands(stack)?;
                 stack.push(b);
                 stack.push(a);
                 Ok(())
             }
             Zero => Ok(stack.push(T::zero())),
             One => Ok(stack.push(T::one())),
+            Two => Ok(stack.push(T::one() + T::one())),
+            NegOne => Ok(stack.push(-T::one())),
             _Phantom(_) => unreachable!(),
         }
     }
@@ -131,6 +269,18 @@ pub enum IntErr<'a> { // TODO change name
     InvalidExpr(&'a str),
 }
 
+impl<'a> fmt::Display for IntErr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IntErr::InvalidExpr(expr) => write!(f, "unknown integer operator: '{}'", expr),
+        }
+    }
+}
+
+impl<'a> Error for IntErr<'a> {}
+
+implement_arithmetic_operators!(IntEvaluator where T: PrimInt + Signed);
+
 impl<'a, T: PrimInt + Signed> TryFromRef<&'a str> for IntEvaluator<T> {
     type Err = IntErr<'a>;
     fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
@@ -143,9 +293,17 @@ impl<'a, T: PrimInt + Signed> TryFromRef<&'a str> for IntEvaluator<T> {
             "%" => Ok(Rem),
             "neg" => Ok(Neg),
             "pow" => Ok(Pow),
+            "mid" => Ok(Midpoint),
+            "absdiff" => Ok(AbsDiff),
+            "next_pow2" => Ok(NextPow2),
+            "is_pow2" => Ok(IsPow2),
+            "ilog2" => Ok(ILog2),
+            "ilog10" => Ok(ILog10),
             "swap" => Ok(Swap),
             "zero" => Ok(Zero),
             "one" => Ok(One),
+            "two" => Ok(Two),
+            "-one" => Ok(NegOne),
             _ => Err(IntErr::InvalidExpr(expr)),
         }
     }
@@ -153,27 +311,88 @@ impl<'a, T: PrimInt + Signed> TryFromRef<&'a str> for IntEvaluator<T> {
 
 impl<T: PrimInt + Signed> fmt::Display for IntEvaluator<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::IntEvaluator::*;
-        let name = match *self {
-            Add => "+",
-            Sub => "-",
-            Mul => "*",
-            Div => "/",
-            Rem => "%",
-            Neg => "neg",
-            Pow => "pow",
-            Swap => "swap",
-            Zero => "zero",
-            One => "one",
-            _Phantom(_) => unreachable!(),
-        };
-        f.write_str(name)
+        f.write_str(Evaluate::<T>::name(self))
+    }
+}
+
+/// Signed Integer Evaluator that wraps an [`IntEvaluator`] and accepts
+/// operators regardless of their case (cf. `"POW"` or `"Neg"`).
+///
+/// [`IntEvaluator`]: enum.IntEvaluator.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CaseInsensitiveIntEvaluator<T: PrimInt + Signed>(pub IntEvaluator<T>);
+
+delegate_evaluate!(CaseInsensitiveIntEvaluator(IntEvaluator) where T: PrimInt + Signed);
+
+impl<'a, T: PrimInt + Signed> TryFromRef<&'a str> for CaseInsensitiveIntEvaluator<T> {
+    type Err = IntErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        with_ascii_lowercase(expr, |lowercase| IntEvaluator::try_from_ref(&lowercase).ok())
+            .flatten()
+            .map(CaseInsensitiveIntEvaluator)
+            .ok_or(IntErr::InvalidExpr(expr))
+    }
+}
+
+impl<T: PrimInt + Signed> FromStr for Expression<T, DummyVariable, IntEvaluator<T>>
+    where for<'a> T: TryFromRef<&'a str>
+{
+    type Err = ParseExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::from_iter(s.split_whitespace()).map_err(|err| {
+            match err {
+                ExprResult::OperandErr(err) => ParseExprError::OperandErr(err),
+                ExprResult::InvalidToken { evaluator: IntErr::InvalidExpr(token), .. } => {
+                    ParseExprError::InvalidToken(token.to_string())
+                }
+            }
+        })
+    }
+}
+
+/// Signed Integer Evaluator that wraps an [`IntEvaluator`] and additionally recognizes
+/// some common aliases of its operators (cf. `"^"` or `"**"` for `"pow"`
+/// and `"mod"` for `"%"`), the canonical names are used when displayed.
+///
+/// [`IntEvaluator`]: enum.IntEvaluator.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AliasedIntEvaluator<T: PrimInt + Signed>(pub IntEvaluator<T>);
+
+impl<T: PrimInt + Signed> IntEvaluator<T> {
+    /// Wraps this evaluator into an [`AliasedIntEvaluator`].
+    ///
+    /// [`AliasedIntEvaluator`]: struct.AliasedIntEvaluator.html
+    pub fn with_aliases(self) -> AliasedIntEvaluator<T> {
+        AliasedIntEvaluator(self)
+    }
+}
+
+impl<T: PrimInt + Signed> AliasedIntEvaluator<T> {
+    fn from_alias(expr: &str) -> Option<IntEvaluator<T>> {
+        match expr {
+            "^" | "**" => Some(IntEvaluator::Pow),
+            "mod" => Some(IntEvaluator::Rem),
+            _ => None,
+        }
+    }
+}
+
+delegate_evaluate!(AliasedIntEvaluator(IntEvaluator) where T: PrimInt + Signed);
+
+impl<'a, T: PrimInt + Signed> TryFromRef<&'a str> for AliasedIntEvaluator<T> {
+    type Err = IntErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        match Self::from_alias(expr) {
+            Some(evaluator) => Ok(evaluator.with_aliases()),
+            None => IntEvaluator::try_from_ref(expr).map(AliasedIntEvaluator),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use expression::{ExprResult, OperandErr};
+    use expression::{ExprResult, OperandErr, ParseExprError};
     use evaluate::{IntErr, IntEvaluateErr, IntExpr};
 
     #[test]
@@ -183,7 +402,7 @@ mod tests {
         let res = IntExpr::<i32>::from_iter(tokens);
         match res {
             Err(ExprResult::InvalidToken { evaluator: IntErr::InvalidExpr("&"), .. }) => (),
-            _ => panic!(res),
+            _ => panic!("{:?}", res),
         }
     }
 
@@ -193,8 +412,8 @@ mod tests {
         let tokens = expr_str.split_whitespace();
         let res = IntExpr::<i32>::from_iter(tokens);
         match res {
-            Err(ExprResult::OperandErr(OperandErr::TooManyOperands)) => (),
-            _ => panic!(res),
+            Err(ExprResult::OperandErr(OperandErr::TooManyOperands { found: 2 })) => (),
+            _ => panic!("{:?}", res),
         }
     }
 
@@ -204,8 +423,8 @@ mod tests {
         let tokens = expr_str.split_whitespace();
         let res = IntExpr::<i32>::from_iter(tokens);
         match res {
-            Err(ExprResult::OperandErr(OperandErr::NotEnoughOperand)) => (),
-            _ => panic!(res),
+            Err(ExprResult::OperandErr(OperandErr::NotEnoughOperand { needed: 2, available: 1 })) => (),
+            _ => panic!("{:?}", res),
         }
     }
 
@@ -233,6 +452,15 @@ mod tests {
         assert_eq!(expr.evaluate(), Ok(1));
     }
 
+    #[test]
+    fn simple_two_and_neg_one() {
+        let expr = IntExpr::<i32>::from_iter("two two *".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(4));
+
+        let expr = IntExpr::<i32>::from_iter("-one neg".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1));
+    }
+
     #[test]
     fn underflowing_substraction() {
         let expr_str = "-120 30 -";
@@ -321,6 +549,115 @@ mod tests {
         assert_eq!(expr.evaluate(), Err(IntEvaluateErr::ConvertToU32(-10)));
     }
 
+    #[test]
+    fn simple_midpoint() {
+        let expr = IntExpr::<i8>::from_iter("120 10 mid".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(65));
+
+        let expr = IntExpr::<i8>::from_iter("127 -127 mid".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0));
+
+        let expr = IntExpr::<i8>::from_iter("127 127 mid".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(127));
+    }
+
+    #[test]
+    fn simple_next_pow2() {
+        let expr = IntExpr::<i32>::from_iter("5 next_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(8));
+
+        let expr = IntExpr::<i32>::from_iter("4 next_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(4));
+
+        let expr = IntExpr::<i32>::from_iter("-3 next_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1));
+    }
+
+    #[test]
+    fn overflowing_next_pow2() {
+        let expr = IntExpr::<i8>::from_iter("64 next_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(64));
+
+        let expr = IntExpr::<i8>::from_iter("65 next_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::NextPow2Overflow(65)));
+    }
+
+    #[test]
+    fn simple_is_pow2() {
+        let expr = IntExpr::<i32>::from_iter("4 is_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1));
+
+        let expr = IntExpr::<i32>::from_iter("3 is_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0));
+
+        let expr = IntExpr::<i32>::from_iter("0 is_pow2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(0));
+    }
+
+    #[test]
+    fn stack_underflow() {
+        use evaluate::{Evaluate, IntEvaluator};
+        use stack::Stack;
+
+        let mut stack = Stack::new();
+        stack.push(3);
+        let res = IntEvaluator::Pow.evaluate(&mut stack);
+        assert_eq!(res, Err(IntEvaluateErr::StackUnderflow));
+
+        let res = IntEvaluator::<i32>::ILog2.evaluate(&mut Stack::new());
+        assert_eq!(res, Err(IntEvaluateErr::StackUnderflow));
+    }
+
+    #[test]
+    fn integer_logarithms() {
+        let expr = IntExpr::<i32>::from_iter("8 ilog2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(3));
+
+        let expr = IntExpr::<i8>::from_iter("127 ilog2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(6));
+
+        let expr = IntExpr::<i32>::from_iter("1000 ilog10".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(3));
+
+        let expr = IntExpr::<i32>::from_iter("999 ilog10".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(2));
+
+        let expr = IntExpr::<i32>::from_iter("0 ilog2".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::InvalidLog(0)));
+
+        let expr = IntExpr::<i32>::from_iter("-10 ilog10".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::InvalidLog(-10)));
+    }
+
+    #[test]
+    fn absolute_difference() {
+        let expr = IntExpr::<i32>::from_iter("10 3 absdiff".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(7));
+
+        let expr = IntExpr::<i32>::from_iter("3 10 absdiff".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(7));
+
+        let expr = IntExpr::<i8>::from_iter("-128 127 absdiff".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::AbsDiffOverflow(-128, 127)));
+    }
+
+    #[test]
+    fn case_insensitive_operators() {
+        use evaluate::CaseInsensitiveIntExpr;
+
+        let expr = CaseInsensitiveIntExpr::<i32>::from_iter("3 4 POW Neg".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(-81));
+    }
+
+    #[test]
+    fn aliased_operators() {
+        use evaluate::AliasedIntExpr;
+
+        let expr = AliasedIntExpr::<i32>::from_iter("3 4 ** 5 mod".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1));
+        assert_eq!(expr.to_string(), "3 4 pow 5 %");
+    }
+
     #[test]
     fn simple_swap() {
         let expr_str = "2 4 swap /";
@@ -352,4 +689,33 @@ mod tests {
         let expr = IntExpr::<i32>::from_iter(tokens).unwrap();
         assert_eq!(&expr.to_string(), expr_str);
     }
+
+    #[test]
+    fn parse_from_str() {
+        let expr = "3 4 +".parse::<IntExpr<i32>>().unwrap();
+        assert_eq!(expr.evaluate(), Ok(7));
+
+        let res = "3 &".parse::<IntExpr<i32>>();
+        assert_eq!(res, Err(ParseExprError::InvalidToken("&".to_string())));
+
+        let res = "3 4".parse::<IntExpr<i32>>();
+        let err = OperandErr::TooManyOperands { found: 2 };
+        assert_eq!(res, Err(ParseExprError::OperandErr(err)));
+    }
+
+    #[test]
+    fn display_errors() {
+        let expr = IntExpr::<i8>::from_iter("125 20 +".split_whitespace()).unwrap();
+        let err = expr.evaluate().unwrap_err();
+        assert_eq!(format!("{}", err), "integer overflow: 125 + 20");
+
+        let err = IntExpr::<i8>::from_iter("3 &".split_whitespace()).unwrap_err();
+        assert!(format!("{}", err).contains("unknown integer operator: '&'"));
+
+        let err = IntExpr::<i8>::from_iter("+".split_whitespace()).unwrap_err();
+        assert_eq!(format!("{}", err), "not enough operands on the stack (2 needed, 0 available)");
+
+        let err = "3 &".parse::<IntExpr<i32>>().unwrap_err();
+        assert_eq!(format!("{}", err), "invalid token: '&'");
+    }
 }
@@ -1,25 +1,184 @@
+#[cfg(feature = "int-evaluator")]
+use core::num::Wrapping;
+use num_complex::Complex;
 use stack::Stack;
 use expression::Expression;
 use variable::DummyVariable;
+#[cfg(any(feature = "float-evaluator", feature = "int-evaluator"))]
+use variable::NamedVar;
 
+/// Implements [`Evaluate`] and `Display` for a wrapper of an evaluator,
+/// delegating everything to the wrapped one, only the parsing differs.
+///
+/// [`Evaluate`]: trait.Evaluate.html
+#[cfg(any(feature = "float-evaluator", feature = "int-evaluator"))]
+macro_rules! delegate_evaluate {
+    ($wrapper:ident($inner:ident) where T: $($bound:tt)+) => {
+        impl<T> Evaluate<T> for $wrapper<T> where T: $($bound)+ {
+            type Err = <$inner<T> as Evaluate<T>>::Err;
+
+            fn operands_needed(&self) -> usize {
+                self.0.operands_needed()
+            }
+
+            fn operands_generated(&self) -> usize {
+                self.0.operands_generated()
+            }
+
+            fn name(&self) -> &'static str {
+                self.0.name()
+            }
+
+            fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
+                self.0.evaluate(stack)
+            }
+        }
+
+        impl<T> fmt::Display for $wrapper<T> where T: $($bound)+ {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+/// Implements [`ArithmeticOperators`] for an evaluator
+/// with `Add`, `Sub`, `Mul` and `Div` variants.
+///
+/// [`ArithmeticOperators`]: trait.ArithmeticOperators.html
+macro_rules! implement_arithmetic_operators {
+    ($evaluator:ident where T: $($bound:tt)+) => {
+        impl<T> ::evaluate::ArithmeticOperators for $evaluator<T> where T: $($bound)+ {
+            fn add() -> Self {
+                $evaluator::Add
+            }
+
+            fn sub() -> Self {
+                $evaluator::Sub
+            }
+
+            fn mul() -> Self {
+                $evaluator::Mul
+            }
+
+            fn div() -> Self {
+                $evaluator::Div
+            }
+        }
+    };
+}
+
+/// Calls `f` with the ASCII lowercase version of `token`, lowercased in a
+/// stack buffer to avoid allocating for every parsed token, `None` is returned
+/// when the token is too long to be the name of any operator.
+#[cfg(any(feature = "float-evaluator", feature = "int-evaluator"))]
+fn with_ascii_lowercase<R, F: FnOnce(&str) -> R>(token: &str, f: F) -> Option<R> {
+    if !token.bytes().any(|b| b.is_ascii_uppercase()) {
+        return Some(f(token))
+    }
+    let mut buffer = [0u8; 16];
+    let buffer = buffer.get_mut(..token.len())?;
+    buffer.copy_from_slice(token.as_bytes());
+    buffer.make_ascii_lowercase();
+    // lowercasing ASCII bytes can't break the UTF-8 encoding
+    core::str::from_utf8(buffer).ok().map(f)
+}
+
+#[cfg(feature = "float-evaluator")]
 mod float;
+mod complex;
+#[cfg(feature = "int-evaluator")]
 mod integer;
+#[cfg(feature = "int-evaluator")]
+mod unsigned;
+#[cfg(feature = "int-evaluator")]
+mod wrapping;
+mod ext;
+mod dyn_eval;
 
-pub use self::float::{FloatEvaluator, FloatErr, FloatEvaluateErr};
-pub use self::integer::{IntEvaluator, IntErr, IntEvaluateErr};
+#[cfg(feature = "float-evaluator")]
+pub use self::float::{FloatEvaluator, StrictFloatEvaluator, CaseInsensitiveFloatEvaluator};
+#[cfg(feature = "float-evaluator")]
+pub use self::float::AliasedFloatEvaluator;
+#[cfg(feature = "float-evaluator")]
+pub use self::float::{FloatErr, FloatEvaluateErr};
+pub use self::complex::{ComplexEvaluator, ComplexErr, ComplexEvaluateErr};
+#[cfg(feature = "int-evaluator")]
+pub use self::integer::{IntEvaluator, CaseInsensitiveIntEvaluator, AliasedIntEvaluator};
+#[cfg(feature = "int-evaluator")]
+pub use self::integer::{IntErr, IntEvaluateErr};
+#[cfg(feature = "int-evaluator")]
+pub use self::unsigned::UintEvaluator;
+#[cfg(feature = "int-evaluator")]
+pub use self::wrapping::WrappingIntEvaluator;
+pub use self::ext::EvaluateExt;
+pub use self::dyn_eval::{DynEvaluate, DynEvaluator, DynEvaluators};
 
 /// An helping alias to make [`Float Expressions`](enum.FloatEvaluator.html).
+#[cfg(feature = "float-evaluator")]
 pub type FloatExpr<T> = Expression<T, DummyVariable, FloatEvaluator<T>>;
 
+/// An helping alias to make [`Strict Float Expressions`](struct.StrictFloatEvaluator.html).
+#[cfg(feature = "float-evaluator")]
+pub type StrictFloatExpr<T> = Expression<T, DummyVariable, StrictFloatEvaluator<T>>;
+
+/// An helping alias to make [`Case Insensitive Float Expressions`](struct.CaseInsensitiveFloatEvaluator.html).
+#[cfg(feature = "float-evaluator")]
+pub type CaseInsensitiveFloatExpr<T> = Expression<T, DummyVariable, CaseInsensitiveFloatEvaluator<T>>;
+
+/// An helping alias to make [`Aliased Float Expressions`](struct.AliasedFloatEvaluator.html).
+#[cfg(feature = "float-evaluator")]
+pub type AliasedFloatExpr<T> = Expression<T, DummyVariable, AliasedFloatEvaluator<T>>;
+
 /// An helping alias to make [`Integer Expressions`](enum.IntEvaluator.html).
+#[cfg(feature = "int-evaluator")]
 pub type IntExpr<T> = Expression<T, DummyVariable, IntEvaluator<T>>;
 
+/// An helping alias to make [`Case Insensitive Integer Expressions`](struct.CaseInsensitiveIntEvaluator.html).
+#[cfg(feature = "int-evaluator")]
+pub type CaseInsensitiveIntExpr<T> = Expression<T, DummyVariable, CaseInsensitiveIntEvaluator<T>>;
+
+/// An helping alias to make [`Aliased Integer Expressions`](struct.AliasedIntEvaluator.html).
+#[cfg(feature = "int-evaluator")]
+pub type AliasedIntExpr<T> = Expression<T, DummyVariable, AliasedIntEvaluator<T>>;
+
+/// An helping alias to make [`Unsigned Integer Expressions`](enum.UintEvaluator.html).
+#[cfg(feature = "int-evaluator")]
+pub type UintExpr<T> = Expression<T, DummyVariable, UintEvaluator<T>>;
+
+/// An helping alias to make [`Wrapping Integer Expressions`](enum.WrappingIntEvaluator.html).
+#[cfg(feature = "int-evaluator")]
+pub type WrappingIntExpr<T> = Expression<Wrapping<T>, DummyVariable, WrappingIntEvaluator<T>>;
+
+/// An helping alias to make [`Complex Expressions`](enum.ComplexEvaluator.html).
+pub type ComplexExpr<T> = Expression<Complex<T>, DummyVariable, ComplexEvaluator<T>>;
+
+/// An helping alias to make [`Dynamic Expressions`](struct.DynEvaluator.html).
+pub type DynExpr<'a, T> = Expression<T, DummyVariable, DynEvaluator<'a, T>>;
+
 /// An helping alias to make variable [`Float Expressions`](enum.FloatEvaluator.html).
+#[cfg(feature = "float-evaluator")]
 pub type VariableFloatExpr<T, V> = Expression<T, V, FloatEvaluator<T>>;
 
 /// An helping alias to make variable [`Integer Expressions`](enum.IntEvaluator.html).
+#[cfg(feature = "int-evaluator")]
 pub type VariableIntExpr<T, V> = Expression<T, V, IntEvaluator<T>>;
 
+/// An helping alias to make variable [`Unsigned Integer Expressions`](enum.UintEvaluator.html).
+#[cfg(feature = "int-evaluator")]
+pub type VariableUintExpr<T, V> = Expression<T, V, UintEvaluator<T>>;
+
+/// An helping alias to make variable [`Complex Expressions`](enum.ComplexEvaluator.html).
+pub type VariableComplexExpr<T, V> = Expression<Complex<T>, V, ComplexEvaluator<T>>;
+
+/// An helping alias to make named variable [`Float Expressions`](enum.FloatEvaluator.html).
+#[cfg(feature = "float-evaluator")]
+pub type VariableFloatExprNamed<T> = Expression<T, NamedVar, FloatEvaluator<T>>;
+
+/// An helping alias to make named variable [`Integer Expressions`](enum.IntEvaluator.html).
+#[cfg(feature = "int-evaluator")]
+pub type VariableIntExprNamed<T> = Expression<T, NamedVar, IntEvaluator<T>>;
+
 /// The main `Trait` allowing evaluation of operations on [`Operands`].
 ///
 /// [`Operands`]: ../expression/enum.Arithm.html
@@ -35,7 +194,41 @@ pub trait Evaluate<T> {
     /// and will `push()` in the `stack`.
     fn operands_generated(&self) -> usize;
 
+    /// Returns a short identifier of this `Evaluator`, like its operator token,
+    /// useful to log or profile evaluations without a `Display` bound.
+    fn name(&self) -> &'static str {
+        ""
+    }
+
     /// Execute the evaluation with the given `stack`,
     /// returns the `Evaluation` error if something goes wrong.
     fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err>;
 }
+
+/// Gives the basic arithmetic operators of an evaluator, used to recognize them
+/// when simplifying expressions (cf. [`Expression::symbolic_simplify()`]).
+///
+/// [`Expression::symbolic_simplify()`]: ../expression/struct.Expression.html#method.symbolic_simplify
+pub trait ArithmeticOperators: Sized {
+    /// Returns the addition operator.
+    fn add() -> Self;
+
+    /// Returns the substraction operator.
+    fn sub() -> Self;
+
+    /// Returns the multiplication operator.
+    fn mul() -> Self;
+
+    /// Returns the division operator.
+    fn div() -> Self;
+}
+
+/// An [`Evaluate`] extension for evaluators needing a mutable state shared
+/// by all the operators of a single evaluation (e.g. a counter or a seed).
+///
+/// [`Evaluate`]: ../evaluate/trait.Evaluate.html
+pub trait EvaluateStateful<T, S>: Evaluate<T> {
+    /// Execute the evaluation with the given `stack` and `state`,
+    /// returns the `Evaluation` error if something goes wrong.
+    fn evaluate_stateful(self, stack: &mut Stack<T>, state: &mut S) -> Result<(), Self::Err>;
+}
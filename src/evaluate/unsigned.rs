@@ -0,0 +1,341 @@
+use core::marker::PhantomData;
+use core::fmt;
+use core::str::FromStr;
+use num_traits::{PrimInt, Unsigned, checked_pow};
+use evaluate::{Evaluate, IntErr, IntEvaluateErr};
+use expression::{Expression, ExprResult, ParseExprError};
+use variable::DummyVariable;
+use stack::Stack;
+use {pop_two_operands, StackUnderflowError};
+use convert_ref::TryFromRef;
+use alloc::string::ToString;
+
+/// Basic Unsigned Integer Evaluator for any type that implement [`PrimInt`] and [`Unsigned`] Traits.
+///
+/// [`PrimInt`]: https://docs.rs/num-traits/0.2/num_traits/int/trait.PrimInt.html
+/// [`Unsigned`]: https://docs.rs/num-traits/0.2/num_traits/sign/trait.Unsigned.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UintEvaluator<T: PrimInt + Unsigned> {
+    /// `"+"` will pop `2` operands and push `1`.
+    Add,
+    /// `"-"` will pop `2` operands and push `1`.
+    Sub,
+    /// `"*"` will pop `2` operands and push `1`.
+    Mul,
+    /// `"/"` will pop `2` operands and push `1`.
+    Div,
+    /// `"%"` will pop `2` operands and push `1`.
+    Rem,
+    /// `"pow"` will pop `2` operands and push `1`.
+    Pow,
+    /// `"&"` will pop `2` operands and push `1`.
+    And,
+    /// `"|"` will pop `2` operands and push `1`.
+    Or,
+    /// `"^"` will pop `2` operands and push `1`.
+    Xor,
+    /// `"!"` will pop `1` operand and push `1`.
+    Not,
+    /// `"swap"` will pop `2` operands and push `2`.
+    Swap,
+    /// `"zero"` will pop `0` operand and push `1`.
+    Zero,
+    /// `"one"` will pop `0` operand and push `1`.
+    One,
+    #[doc(hidden)]
+    _Phantom(PhantomData<T>),
+}
+
+impl<T: PrimInt + Unsigned> Evaluate<T> for UintEvaluator<T> {
+    type Err = IntEvaluateErr<T>;
+
+    fn operands_needed(&self) -> usize {
+        use self::UintEvaluator::*;
+        match *self {
+            Add | Sub | Mul | Div | Pow | Rem | And | Or | Xor | Swap => 2,
+            Not => 1,
+            Zero | One => 0,
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    fn operands_generated(&self) -> usize {
+        use self::UintEvaluator::*;
+        match *self {
+            Add | Sub | Mul | Div | Rem | Pow | And | Or | Xor | Not | Zero | One => 1,
+            Swap => 2,
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        use self::UintEvaluator::*;
+        match *self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            Pow => "pow",
+            And => "&",
+            Or => "|",
+            Xor => "^",
+            Not => "!",
+            Swap => "swap",
+            Zero => "zero",
+            One => "one",
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    #[allow(clippy::unit_arg)]
+    fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
+        use self::UintEvaluator::*;
+        use self::IntEvaluateErr::*;
+        match self {
+            Add => {
+                let (a, b) = pop_two_operands(stack)?;
+                let c = a.checked_add(&b).ok_or(AddOverflow(a, b))?;
+                Ok(stack.push(c))
+            }
+            Sub => {
+                let (a, b) = pop_two_operands(stack)?;
+                let c = a.checked_sub(&b).ok_or(SubUnderflow(a, b))?;
+                Ok(stack.push(c))
+            }
+            Mul => {
+                let (a, b) = pop_two_operands(stack)?;
+                let c = a.checked_mul(&b).ok_or(MulOverflow(a, b))?;
+                Ok(stack.push(c))
+            }
+            Div => {
+                let (a, b) = pop_two_operands(stack)?;
+                let c = a.checked_div(&b).ok_or(InvalidDiv(a, b))?;
+                Ok(stack.push(c))
+            }
+            Rem => {
+                let (a, b) = pop_two_operands(stack)?;
+                if b == T::zero() {
+                    Err(InvalidRem(a, b))
+                } else {
+                    Ok(stack.push(a % b))
+                }
+            }
+            Pow => {
+                let (a, b) = pop_two_operands(stack)?;
+                let b = b.to_usize().ok_or(ConvertToU32(b))?;
+                let pow = checked_pow(a, b).ok_or(PowOverflow(a, b))?;
+                Ok(stack.push(pow))
+            }
+            And => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(a & b))
+            }
+            Or => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(a | b))
+            }
+            Xor => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(a ^ b))
+            }
+            Not => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(!a))
+            }
+            Swap => {
+                let (a, b) = pop_two_operands(stack)?;
+                stack.push(b);
+                stack.push(a);
+                Ok(())
+            }
+            Zero => Ok(stack.push(T::zero())),
+            One => Ok(stack.push(T::one())),
+            _Phantom(_) => unreachable!(),
+        }
+    }
+}
+
+implement_arithmetic_operators!(UintEvaluator where T: PrimInt + Unsigned);
+
+impl<'a, T: PrimInt + Unsigned> TryFromRef<&'a str> for UintEvaluator<T> {
+    type Err = IntErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        use self::UintEvaluator::*;
+        match *expr {
+            "+" => Ok(Add),
+            "-" => Ok(Sub),
+            "*" => Ok(Mul),
+            "/" => Ok(Div),
+            "%" => Ok(Rem),
+            "pow" => Ok(Pow),
+            "&" => Ok(And),
+            "|" => Ok(Or),
+            "^" => Ok(Xor),
+            "!" => Ok(Not),
+            "swap" => Ok(Swap),
+            "zero" => Ok(Zero),
+            "one" => Ok(One),
+            _ => Err(IntErr::InvalidExpr(expr)),
+        }
+    }
+}
+
+impl<T: PrimInt + Unsigned> fmt::Display for UintEvaluator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(Evaluate::<T>::name(self))
+    }
+}
+
+impl<T: PrimInt + Unsigned> FromStr for Expression<T, DummyVariable, UintEvaluator<T>>
+    where for<'a> T: TryFromRef<&'a str>
+{
+    type Err = ParseExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::from_iter(s.split_whitespace()).map_err(|err| {
+            match err {
+                ExprResult::OperandErr(err) => ParseExprError::OperandErr(err),
+                ExprResult::InvalidToken { evaluator: IntErr::InvalidExpr(token), .. } => {
+                    ParseExprError::InvalidToken(token.to_string())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expression::{ExprResult, OperandErr, ParseExprError};
+    use evaluate::{IntErr, IntEvaluateErr, UintExpr};
+
+    #[test]
+    fn bad_operator() {
+        let expr_str = "3 4 + neg";
+        let tokens = expr_str.split_whitespace();
+        let res = UintExpr::<u32>::from_iter(tokens);
+        match res {
+            Err(ExprResult::InvalidToken { evaluator: IntErr::InvalidExpr("neg"), .. }) => (),
+            _ => panic!("{:?}", res),
+        }
+    }
+
+    #[test]
+    fn too_many_operands() {
+        let expr_str = "3 3 4 +";
+        let tokens = expr_str.split_whitespace();
+        let res = UintExpr::<u32>::from_iter(tokens);
+        match res {
+            Err(ExprResult::OperandErr(OperandErr::TooManyOperands { found: 2 })) => (),
+            _ => panic!("{:?}", res),
+        }
+    }
+
+    #[test]
+    fn simple_addition() {
+        let expr_str = "3 4 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(7));
+    }
+
+    #[test]
+    fn overflowing_addition() {
+        let expr_str = "4294967295 1 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::AddOverflow(4294967295, 1)));
+    }
+
+    #[test]
+    fn simple_substraction() {
+        let expr_str = "4 3 -";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(1));
+    }
+
+    #[test]
+    fn underflowing_substraction() {
+        let expr_str = "3 4 -";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::SubUnderflow(3, 4)));
+    }
+
+    #[test]
+    fn simple_multiplication() {
+        let expr_str = "3 4 *";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(12));
+    }
+
+    #[test]
+    fn invalid_division() {
+        let expr_str = "9 0 /";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::InvalidDiv(9, 0)));
+    }
+
+    #[test]
+    fn invalid_remaining() {
+        let expr_str = "9 0 %";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::InvalidRem(9, 0)));
+    }
+
+    #[test]
+    fn overflowing_power() {
+        let expr_str = "3 30 pow";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::PowOverflow(3, 30)));
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        let expr_str = "12 10 & 1 | 3 ^ !";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u8>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(!((12 & 10 | 1) ^ 3)));
+    }
+
+    #[test]
+    fn simple_swap() {
+        let expr_str = "2 4 swap /";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(2));
+    }
+
+    #[test]
+    fn to_string() {
+        let expr_str = "3 3 + ! 4 & one swap -";
+        let tokens = expr_str.split_whitespace();
+        let expr = UintExpr::<u32>::from_iter(tokens).unwrap();
+        assert_eq!(&expr.to_string(), expr_str);
+    }
+
+    #[test]
+    fn parse_from_str() {
+        let expr = "3 4 +".parse::<UintExpr<u32>>().unwrap();
+        assert_eq!(expr.evaluate(), Ok(7));
+
+        let res = "3 neg".parse::<UintExpr<u32>>();
+        assert_eq!(res, Err(ParseExprError::InvalidToken("neg".to_string())));
+    }
+
+    #[test]
+    fn variable_expression() {
+        use evaluate::VariableUintExpr;
+        use variable::IndexVar;
+
+        let expr_str = "$0 $1 -";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableUintExpr::<u32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_with_variables(&vec![5, 3]), Ok(2));
+    }
+}
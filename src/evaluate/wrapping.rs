@@ -0,0 +1,199 @@
+use core::marker::PhantomData;
+use core::fmt;
+use core::num::Wrapping;
+use num_traits::{PrimInt, CheckedRem, WrappingAdd, WrappingSub, WrappingMul, WrappingNeg};
+use evaluate::{Evaluate, IntErr, IntEvaluateErr};
+use stack::Stack;
+use {pop_two_operands, StackUnderflowError};
+use convert_ref::TryFromRef;
+
+/// Integer Evaluator working on [`Wrapping`] operands, overflowing
+/// operations wrap around the boundary of the type instead of failing.
+///
+/// [`Wrapping`]: https://doc.rust-lang.org/std/num/struct.Wrapping.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WrappingIntEvaluator<T: PrimInt> {
+    /// `"+"` will pop `2` operands and push `1`.
+    Add,
+    /// `"-"` will pop `2` operands and push `1`.
+    Sub,
+    /// `"*"` will pop `2` operands and push `1`.
+    Mul,
+    /// `"/"` will pop `2` operands and push `1`.
+    Div,
+    /// `"%"` will pop `2` operands and push `1`.
+    Rem,
+    /// `"neg"` will pop `1` operand and push `1`.
+    Neg,
+    /// `"swap"` will pop `2` operands and push `2`.
+    Swap,
+    /// `"zero"` will pop `0` operand and push `1`.
+    Zero,
+    /// `"one"` will pop `0` operand and push `1`.
+    One,
+    #[doc(hidden)]
+    _Phantom(PhantomData<T>),
+}
+
+impl<T> Evaluate<Wrapping<T>> for WrappingIntEvaluator<T>
+    where T: PrimInt + WrappingAdd + WrappingSub + WrappingMul + WrappingNeg + CheckedRem
+{
+    type Err = IntEvaluateErr<Wrapping<T>>;
+
+    fn operands_needed(&self) -> usize {
+        use self::WrappingIntEvaluator::*;
+        match *self {
+            Add | Sub | Mul | Div | Rem | Swap => 2,
+            Neg => 1,
+            Zero | One => 0,
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    fn operands_generated(&self) -> usize {
+        use self::WrappingIntEvaluator::*;
+        match *self {
+            Add | Sub | Mul | Div | Rem | Neg | Zero | One => 1,
+            Swap => 2,
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        use self::WrappingIntEvaluator::*;
+        match *self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            Neg => "neg",
+            Swap => "swap",
+            Zero => "zero",
+            One => "one",
+            _Phantom(_) => unreachable!(),
+        }
+    }
+
+    #[allow(clippy::unit_arg)]
+    fn evaluate(self, stack: &mut Stack<Wrapping<T>>) -> Result<(), Self::Err> {
+        use self::WrappingIntEvaluator::*;
+        use self::IntEvaluateErr::*;
+        match self {
+            Add => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(Wrapping(a.0.wrapping_add(&b.0))))
+            }
+            Sub => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(Wrapping(a.0.wrapping_sub(&b.0))))
+            }
+            Mul => {
+                let (a, b) = pop_two_operands(stack)?;
+                Ok(stack.push(Wrapping(a.0.wrapping_mul(&b.0))))
+            }
+            Div => {
+                let (a, b) = pop_two_operands(stack)?;
+                if b.0 == T::zero() {
+                    return Err(InvalidDiv(a, b))
+                }
+                // the only overflowing division is `MIN / -1`, which wraps to `MIN`
+                Ok(stack.push(Wrapping(a.0.checked_div(&b.0).unwrap_or(a.0))))
+            }
+            Rem => {
+                let (a, b) = pop_two_operands(stack)?;
+                if b.0 == T::zero() {
+                    return Err(InvalidRem(a, b))
+                }
+                // the only overflowing remainder is `MIN % -1`, which wraps to `0`
+                let c = CheckedRem::checked_rem(&a.0, &b.0).unwrap_or_else(T::zero);
+                Ok(stack.push(Wrapping(c)))
+            }
+            Neg => {
+                let a = stack.pop().ok_or(StackUnderflowError)?;
+                Ok(stack.push(Wrapping(a.0.wrapping_neg())))
+            }
+            Swap => {
+                let (a, b) = pop_two_operands(stack)?;
+                stack.push(b);
+                stack.push(a);
+                Ok(())
+            }
+            Zero => Ok(stack.push(Wrapping(T::zero()))),
+            One => Ok(stack.push(Wrapping(T::one()))),
+            _Phantom(_) => unreachable!(),
+        }
+    }
+}
+
+implement_arithmetic_operators!(WrappingIntEvaluator where T: PrimInt);
+
+impl<'a, T: PrimInt> TryFromRef<&'a str> for WrappingIntEvaluator<T> {
+    type Err = IntErr<'a>;
+    fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+        use self::WrappingIntEvaluator::*;
+        match *expr {
+            "+" => Ok(Add),
+            "-" => Ok(Sub),
+            "*" => Ok(Mul),
+            "/" => Ok(Div),
+            "%" => Ok(Rem),
+            "neg" => Ok(Neg),
+            "swap" => Ok(Swap),
+            "zero" => Ok(Zero),
+            "one" => Ok(One),
+            _ => Err(IntErr::InvalidExpr(expr)),
+        }
+    }
+}
+
+impl<T> fmt::Display for WrappingIntEvaluator<T>
+    where T: PrimInt + WrappingAdd + WrappingSub + WrappingMul + WrappingNeg + CheckedRem
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(Evaluate::<Wrapping<T>>::name(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::Wrapping;
+    use evaluate::{IntEvaluateErr, WrappingIntExpr};
+
+    #[test]
+    fn wrapping_addition() {
+        let expr = WrappingIntExpr::<i8>::from_iter("127 1 +".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Wrapping(-128)));
+    }
+
+    #[test]
+    fn wrapping_substraction() {
+        let expr = WrappingIntExpr::<u8>::from_iter("3 4 -".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Wrapping(255)));
+    }
+
+    #[test]
+    fn wrapping_division() {
+        let expr = WrappingIntExpr::<i8>::from_iter("-128 -1 /".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Wrapping(-128)));
+
+        let expr = WrappingIntExpr::<i8>::from_iter("-128 -1 %".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Wrapping(0)));
+
+        let expr = WrappingIntExpr::<i8>::from_iter("9 0 /".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Err(IntEvaluateErr::InvalidDiv(Wrapping(9), Wrapping(0))));
+    }
+
+    #[test]
+    fn wrapping_negation() {
+        let expr = WrappingIntExpr::<i8>::from_iter("-128 neg".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Wrapping(-128)));
+    }
+
+    #[test]
+    fn to_string() {
+        let expr_str = "127 1 + neg 3 swap %";
+        let expr = WrappingIntExpr::<i8>::from_iter(expr_str.split_whitespace()).unwrap();
+        assert_eq!(&expr.to_string(), expr_str);
+    }
+}
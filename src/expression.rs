@@ -1,17 +1,76 @@
-use std::fmt;
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Add, Range};
+use core::iter::Peekable;
+use core::str::SplitWhitespace;
+use core::slice;
+use alloc::vec;
 use stack::Stack;
-use evaluate::Evaluate;
-use variable::{GetVariable, DummyVariables};
+use evaluate::{ArithmeticOperators, Evaluate, EvaluateStateful};
+use variable::{GetVariableOwned, GetVariableMut, GetVariableStr, DummyVariables};
 use convert_ref::{TryFromRef, TryIntoRef};
+use alloc::collections::BTreeMap;
+use num_traits::{Float, One, Zero};
+use alloc::vec::Vec;
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
 
 /// Used to specify an `Operand` or an `Evaluator`.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Arithm<T, V, E: Evaluate<T>> {
     Operand(T),
     Variable(V),
     Evaluator(E),
 }
 
+impl<T, V, E: Evaluate<T>> Arithm<T, V, E> {
+    /// Returns `true` if the token is an `Operand`.
+    pub fn is_operand(&self) -> bool {
+        self.as_operand().is_some()
+    }
+
+    /// Returns `true` if the token is a `Variable`.
+    pub fn is_variable(&self) -> bool {
+        self.as_variable().is_some()
+    }
+
+    /// Returns `true` if the token is an `Evaluator`.
+    pub fn is_evaluator(&self) -> bool {
+        self.as_evaluator().is_some()
+    }
+
+    /// Returns the operand or `None` if the token is not an `Operand`.
+    pub fn as_operand(&self) -> Option<&T> {
+        match *self {
+            Arithm::Operand(ref operand) => Some(operand),
+            _ => None,
+        }
+    }
+
+    /// Returns the variable or `None` if the token is not a `Variable`.
+    pub fn as_variable(&self) -> Option<&V> {
+        match *self {
+            Arithm::Variable(ref variable) => Some(variable),
+            _ => None,
+        }
+    }
+
+    /// Returns the evaluator or `None` if the token is not an `Evaluator`.
+    pub fn as_evaluator(&self) -> Option<&E> {
+        match *self {
+            Arithm::Evaluator(ref evaluator) => Some(evaluator),
+            _ => None,
+        }
+    }
+}
+
+/// The comment character conventionally used with [`Expression::from_str_with_comments()`].
+///
+/// [`Expression::from_str_with_comments()`]: struct.Expression.html#method.from_str_with_comments
+pub const DEFAULT_COMMENT_CHAR: char = '#';
+
 /// Interpret a [`Reverse Polish notated`] expression (cf. `3 4 +`).
 ///
 /// `Evaluate` method returns the valid result or an [`Evaluate::Err`]
@@ -25,7 +84,7 @@ pub enum Arithm<T, V, E: Evaluate<T>> {
 /// [`Evaluate::Err`]: ../evaluate/trait.Evaluate.html#associatedtype.Err
 /// [`str`]: https://doc.rust-lang.org/std/str/index.html
 /// [`try_into_ref()`]: ../convert_ref/trait.TryIntoRef.html
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Expression<T, V, E: Evaluate<T>> {
     max_stack: usize,
     expr: Vec<Arithm<T, V, E>>,
@@ -35,9 +94,25 @@ pub struct Expression<T, V, E: Evaluate<T>> {
 pub enum EvalErr<V, E> {
     VariableNotFound(V),
     EvalError(E),
+    /// The evaluation would need more than `limit` values on the stack.
+    StackOverflow { limit: usize },
+}
+
+impl<V: fmt::Display, E: fmt::Display> fmt::Display for EvalErr<V, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EvalErr::VariableNotFound(ref var) => write!(f, "variable not found: {}", var),
+            EvalErr::EvalError(ref err) => err.fmt(f),
+            EvalErr::StackOverflow { limit } => {
+                write!(f, "evaluation needs more than {} values on the stack", limit)
+            }
+        }
+    }
 }
 
-impl<T: Copy, V: Copy, E: Evaluate<T> + Copy> Expression<T, V, E> {
+impl<V: fmt::Debug + fmt::Display, E: Error> Error for EvalErr<V, E> {}
+
+impl<T: Copy, V: Clone, E: Evaluate<T> + Copy> Expression<T, V, E> {
     /// Evaluate `RPN` expressions. Returns the result
     /// or the [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
     pub fn evaluate(&self) -> Result<T, E::Err>
@@ -52,155 +127,3463 @@ impl<T: Copy, V: Copy, E: Evaluate<T> + Copy> Expression<T, V, E> {
             })
     }
 
+    /// Evaluates both expressions and returns `true` if their results
+    /// differ by less than `epsilon`, `false` if one of the evaluations fails.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::FloatExpr;
+    ///
+    /// let expr = FloatExpr::<f32>::from_iter("1 3 /".split_whitespace()).unwrap();
+    /// let expected = FloatExpr::<f32>::from_iter("0.333333".split_whitespace()).unwrap();
+    /// assert!(expr.approximate_equal(&expected, 1e-5));
+    /// # }
+    /// ```
+    pub fn approximate_equal(&self, other: &Self, epsilon: T) -> bool
+        where T: Float,
+              (): From<V>
+    {
+        match (self.evaluate(), other.evaluate()) {
+            (Ok(a), Ok(b)) => (a - b).abs() < epsilon,
+            _ => false,
+        }
+    }
+
     /// Evaluate `RPN` expressions containing variables. Returns the result
     /// or the [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
-    pub fn evaluate_with_variables<I, C>(&self, variables: &C) -> Result<T, EvalErr<V, E::Err>>
+    pub fn evaluate_with_variables<I, M, C>(&self, variables: &C) -> Result<T, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        let mut stack = self.evaluate_to_stack(variables)?;
+        Ok(stack.pop().unwrap())
+    }
+
+    /// Same as [`evaluate_with_variables()`] but retrieves the variables by name from
+    /// a [`GetVariableStr`] container, like an `HashMap<String, T>`, so that no `String`
+    /// is allocated for each variable access.
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "std", feature = "float-evaluator"))] {
+    /// use std::collections::HashMap;
+    /// use ripin::evaluate::VariableFloatExprNamed;
+    ///
+    /// let mut variables = HashMap::new();
+    /// variables.insert("x".to_string(), 3.0);
+    /// let expr = VariableFloatExprNamed::<f32>::from_iter("$x $x *".split_whitespace()).unwrap();
+    /// assert_eq!(expr.evaluate_with_str_variables(&variables), Ok(9.0));
+    /// # }
+    /// ```
+    ///
+    /// [`evaluate_with_variables()`]: #method.evaluate_with_variables
+    /// [`GetVariableStr`]: ../variable/trait.GetVariableStr.html
+    pub fn evaluate_with_str_variables<C>(&self, variables: &C) -> Result<T, EvalErr<V, E::Err>>
+        where V: AsRef<str>,
+              C: GetVariableStr<Output=T> + ?Sized
+    {
+        let lookup = |var: &V| variables.get_variable_str(var.as_ref()).cloned();
+        let mut stack = self.evaluate_inspect(lookup, |_, _| ())?;
+        Ok(stack.pop().unwrap())
+    }
+
+    /// Same as [`evaluate_with_variables()`] but reuses the given `stack`,
+    /// avoiding an allocation for each evaluation. The `stack` is cleared before use.
+    ///
+    /// The capacity of the `stack` should be at least [`max_stack_depth()`]
+    /// for the evaluation to never reallocate.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::Stack;
+    /// use ripin::evaluate::VariableFloatExpr;
+    /// use ripin::variable::IndexVar;
+    ///
+    /// let tokens = "$0 2 *".split_whitespace();
+    /// let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+    /// let mut stack = Stack::with_capacity(expr.max_stack_depth());
+    /// for i in 0..10 {
+    ///     let value = i as f32;
+    ///     assert_eq!(expr.evaluate_with_stack(&[value], &mut stack), Ok(value * 2.0));
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`evaluate_with_variables()`]: #method.evaluate_with_variables
+    /// [`max_stack_depth()`]: #method.max_stack_depth
+    pub fn evaluate_with_stack<I, M, C>(&self, variables: &C, stack: &mut Stack<T>)
+                                        -> Result<T, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        stack.clear();
+        let lookup = |var: &V| variables.get_variable_owned(var.clone().into());
+        self.evaluate_into(stack, lookup, |_, _, _| (), |_, _| ())?;
+        Ok(stack.pop().unwrap())
+    }
+
+    /// Evaluate `RPN` expressions with [`stateful evaluators`], the `context`
+    /// is given to each of them in turn. Returns the result
+    /// or the [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
+    ///
+    /// [`stateful evaluators`]: ../evaluate/trait.EvaluateStateful.html
+    pub fn evaluate_with_context<S>(&self, context: &mut S) -> Result<T, E::Err>
+        where (): From<V>,
+              E: EvaluateStateful<T, S>
+    {
+        let mut stack = Stack::with_capacity(self.max_stack);
+        for arithm in &self.expr {
+            match *arithm {
+                Arithm::Operand(operand) => stack.push(operand),
+                Arithm::Variable(_) => unreachable!(),
+                Arithm::Evaluator(evaluator) => evaluator.evaluate_stateful(&mut stack, context)?,
+            }
+        }
+        Ok(stack.pop().unwrap())
+    }
+
+    /// Same as [`evaluate_with_variables()`] but fails without evaluating anything if
+    /// the expression needs more than `limit` values on the stack, useful with untrusted input.
+    ///
+    /// The stack depth needed is computed once at parse time, so this check has no cost.
+    ///
+    /// [`evaluate_with_variables()`]: #method.evaluate_with_variables
+    pub fn evaluate_with_stack_limit<I, M, C>(&self, variables: &C, limit: usize)
+                                              -> Result<T, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        if self.max_stack > limit {
+            return Err(EvalErr::StackOverflow { limit })
+        }
+        self.evaluate_with_variables(variables)
+    }
+
+    /// Evaluate `RPN` expressions containing variables, the `default` value
+    /// is used for the variables not found in the container.
+    /// Returns the result or the [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
+    pub fn evaluate_with_fallback<I, M, C>(&self, variables: &C, default: T) -> Result<T, E::Err>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        let lookup = |var: &V| {
+            Some(variables.get_variable_owned(var.clone().into()).unwrap_or(default))
+        };
+        self.evaluate_inspect(lookup, |_, _| ())
+            .map(|mut stack| stack.pop().unwrap())
+            .map_err(|err| {
+                match err {
+                    EvalErr::EvalError(err) => err,
+                    _ => unreachable!(),
+                }
+            })
+    }
+
+    /// Evaluate `RPN` expressions without stopping at the first error, useful to report
+    /// all the problems of a formula at once. The `neutral` value replaces the values
+    /// a failing evaluator or a missing variable should have pushed on the stack.
+    ///
+    /// Returns the result computed with these replacements along with the errors
+    /// and the index of the token which produced each of them.
+    ///
+    /// ```
+    /// # #[cfg(feature = "int-evaluator")] {
+    /// use ripin::evaluate::{IntEvaluateErr, IntExpr};
+    /// use ripin::expression::EvalErr;
+    /// use ripin::variable::DummyVariables;
+    ///
+    /// let expr = IntExpr::<i32>::from_iter("1 0 / 2 0 / 3 + +".split_whitespace()).unwrap();
+    /// let (result, errors) = expr.evaluate_collecting_errors(&DummyVariables::default(), 0);
+    /// assert_eq!(result, 3);
+    /// assert_eq!(errors, vec![(2, EvalErr::EvalError(IntEvaluateErr::InvalidDiv(1, 0))),
+    ///                         (5, EvalErr::EvalError(IntEvaluateErr::InvalidDiv(2, 0)))]);
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn evaluate_collecting_errors<I, M, C>(&self, variables: &C, neutral: T)
+                                               -> (T, Vec<(usize, EvalErr<V, E::Err>)>)
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        let mut stack = Stack::with_capacity(self.max_stack);
+        let mut errors = Vec::new();
+        for (index, arithm) in self.expr.iter().enumerate() {
+            match *arithm {
+                Arithm::Operand(operand) => stack.push(operand),
+                Arithm::Variable(ref var) => {
+                    match variables.get_variable_owned(var.clone().into()) {
+                        Some(value) => stack.push(value),
+                        None => {
+                            errors.push((index, EvalErr::VariableNotFound(var.clone())));
+                            stack.push(neutral)
+                        }
+                    }
+                }
+                Arithm::Evaluator(evaluator) => {
+                    let remaining = stack.len() - evaluator.operands_needed();
+                    let generated = evaluator.operands_generated();
+                    if let Err(err) = evaluator.evaluate(&mut stack) {
+                        errors.push((index, EvalErr::EvalError(err)));
+                        while stack.len() > remaining {
+                            stack.pop();
+                        }
+                        while stack.len() < remaining + generated {
+                            stack.push(neutral)
+                        }
+                    }
+                }
+            }
+        }
+        (stack.pop().unwrap(), errors)
+    }
+
+    /// Evaluate `RPN` expressions retrieving variables through a mutable container,
+    /// the container is given to each [`stateful evaluator`] which can update
+    /// the variables during the evaluation (e.g. accumulators).
+    /// Returns the result or the [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
+    ///
+    /// [`stateful evaluator`]: ../evaluate/trait.EvaluateStateful.html
+    pub fn evaluate_with_variables_mut<I, C>(&self, variables: &mut C)
+                                             -> Result<T, EvalErr<V, E::Err>>
         where V: Into<I>,
-              C: GetVariable<I, Output=T>
+              C: GetVariableMut<I, Output=T>,
+              E: EvaluateStateful<T, C>
     {
         let mut stack = Stack::with_capacity(self.max_stack);
         for arithm in &self.expr {
             match *arithm {
                 Arithm::Operand(operand) => stack.push(operand),
-                Arithm::Variable(var) => {
-                    let var = variables.get_variable(var.into())
-                        .ok_or_else(|| EvalErr::VariableNotFound(var))?;
-                    stack.push(*var)
+                Arithm::Variable(ref var) => {
+                    match variables.get_variable_mut(var.clone().into()) {
+                        Some(value) => stack.push(*value),
+                        None => return Err(EvalErr::VariableNotFound(var.clone())),
+                    }
                 }
                 Arithm::Evaluator(evaluator) => {
-                    evaluator.evaluate(&mut stack)
-                        .map_err(|err| EvalErr::EvalError(err))?
+                    evaluator.evaluate_stateful(&mut stack, variables).map_err(EvalErr::EvalError)?
                 }
             }
         }
         Ok(stack.pop().unwrap())
     }
-}
 
-impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
-    pub fn from_iter<A, I>(iter: I)
-                           -> Result<Expression<T, V, E>,
-                                     ExprResult<<E as TryFromRef<A>>::Err,
-                                                <V as TryFromRef<A>>::Err,
-                                                <T as TryFromRef<A>>::Err>>
-        where T: TryFromRef<A>,
-              V: TryFromRef<A>,
-              E: TryFromRef<A>,
-              I: IntoIterator<Item=A>
+    /// Converts the expression into a closure evaluating it with the given variables,
+    /// the stack is allocated once and reused between calls.
+    ///
+    /// The closure owns its stack, so it is a `FnMut` and can't be called re-entrantly;
+    /// compile one closure per thread to evaluate in parallel.
+    pub fn compile<I, M, C>(self) -> impl FnMut(&C) -> Result<T, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
     {
-        let final_expr: Result<Vec<_>, _> = iter.into_iter().map(|token| {
-            match TryIntoRef::<E>::try_into_ref(&token) {
-                Ok(eval) => Ok(Arithm::Evaluator(eval)),
-                Err(eval_err) => {
-                    match TryIntoRef::<V>::try_into_ref(&token) {
-                        Ok(var) => Ok(Arithm::Variable(var)),
-                        Err(var_err) => {
-                            match TryIntoRef::<T>::try_into_ref(&token) {
-                                Ok(op) => Ok(Arithm::Operand(op)),
-                                Err(op_err) => Err(ExprResult::InvalidToken {
-                                    evaluator: eval_err,
-                                    variable: var_err,
-                                    operand: op_err,
-                                })
-                             }
-                         }
-                     }
-                 }
-            }
-        }).collect();
-        final_expr.and_then(|final_expr| {
-            match Expression::check_validity(&final_expr) {
-                Ok(_) => Ok(Expression {
-                    max_stack: Expression::compute_stack_max(&final_expr),
-                    expr: final_expr,
-                }),
-                Err(err) => Err(ExprResult::OperandErr(err)),
+        let mut stack = Stack::with_capacity(self.max_stack);
+        move |variables: &C| {
+            stack.clear();
+            let lookup = |var: &V| variables.get_variable_owned(var.clone().into());
+            self.evaluate_into(&mut stack, lookup, |_, _, _| (), |_, _| ())?;
+            Ok(stack.pop().unwrap())
+        }
+    }
+
+    /// Returns an iterator evaluating the expression one token at a time,
+    /// yielding an [`EvalStep`] for each of them. The iteration stops
+    /// after the final result or after the first error.
+    ///
+    /// [`EvalStep`]: ../expression/enum.EvalStep.html
+    pub fn evaluate_steps<'a, I, M, C>(&'a self, variables: &'a C) -> EvalSteps<'a, T, V, E, I, M, C>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        EvalSteps {
+            tokens: self.expr.iter(),
+            variables,
+            stack: Stack::with_capacity(self.max_stack),
+            finished: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Evaluate `RPN` expressions once for each of the `variable_sets`,
+    /// results are returned in the same order.
+    pub fn evaluate_batch<I, M, C>(&self, variable_sets: &[C]) -> Vec<Result<T, EvalErr<V, E::Err>>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T>
+    {
+        variable_sets.iter().map(|variables| self.evaluate_with_variables(variables)).collect()
+    }
+
+    /// Evaluate `RPN` expressions containing variables, `callback` is called
+    /// before each token with its index, the token and the current stack.
+    /// Returns the result or the [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
+    pub fn evaluate_with_step_callback<I, M, C, F>(&self, variables: &C, callback: F)
+                                                   -> Result<T, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized,
+              F: FnMut(usize, &Arithm<T, V, E>, &Stack<T>)
+    {
+        let lookup = |var: &V| variables.get_variable_owned(var.clone().into());
+        let mut stack = Stack::with_capacity(self.max_stack);
+        self.evaluate_into(&mut stack, lookup, callback, |_, _| ())?;
+        Ok(stack.pop().unwrap())
+    }
+
+    /// Evaluate `RPN` expressions and returns the result along with
+    /// a snapshot of the stack (from the bottom to the top) after each token.
+    pub fn evaluate_trace(&self) -> Result<(T, Vec<Vec<T>>), E::Err>
+        where (): From<V>
+    {
+        let mut snapshots = Vec::with_capacity(self.expr.len());
+        self.evaluate_inspect(|_| None, |_, stack| {
+            snapshots.push(stack.as_slice().to_vec())
+        })
+        .map(|mut stack| (stack.pop().unwrap(), snapshots))
+        .map_err(|err| {
+            match err {
+                EvalErr::EvalError(err) => err,
+                _ => unreachable!(),
             }
         })
     }
-}
 
-/// Used to specify the error during the conversion.
-#[derive(Debug, PartialEq)]
-pub enum ExprResult<A, B, C> {
-    OperandErr(OperandErr),
-    InvalidToken {
-        evaluator: A,
-        variable: B,
-        operand: C,
-    },
-}
+    fn evaluate_to_stack<I, M, C>(&self, variables: &C) -> Result<Stack<T>, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        let lookup = |var: &V| variables.get_variable_owned(var.clone().into());
+        self.evaluate_inspect(lookup, |_, _| ())
+    }
 
-/// Used to specify an error related to wrong number of operands in expression.
-#[derive(Debug, PartialEq)]
-pub enum OperandErr {
-    TooManyOperands,
-    NotEnoughOperand,
-}
+    fn evaluate_inspect<L, F>(&self, lookup: L, inspect: F)
+                              -> Result<Stack<T>, EvalErr<V, E::Err>>
+        where L: FnMut(&V) -> Option<T>,
+              F: FnMut(&Arithm<T, V, E>, &Stack<T>)
+    {
+        let mut stack = Stack::with_capacity(self.max_stack);
+        self.evaluate_into(&mut stack, lookup, |_, _, _| (), inspect)?;
+        Ok(stack)
+    }
 
-impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
-    fn check_validity(expr: &[Arithm<T, V, E>]) -> Result<(), OperandErr> {
-        // TODO https://doc.rust-lang.org/1.2.0/std/result/fn.fold.html
-        use self::OperandErr::*;
-        let mut num_operands: usize = 0;
-        for arithm in expr {
+    /// Evaluates the tokens on the given `stack`, `before` is called before each token
+    /// with its index and `inspect` after it.
+    fn evaluate_into<L, B, F>(&self, stack: &mut Stack<T>, mut lookup: L,
+                              mut before: B, mut inspect: F)
+                              -> Result<(), EvalErr<V, E::Err>>
+        where L: FnMut(&V) -> Option<T>,
+              B: FnMut(usize, &Arithm<T, V, E>, &Stack<T>),
+              F: FnMut(&Arithm<T, V, E>, &Stack<T>)
+    {
+        for (index, arithm) in self.expr.iter().enumerate() {
+            before(index, arithm, stack);
             match *arithm {
-                Arithm::Operand(_) |
-                Arithm::Variable(_) => num_operands += 1,
-                Arithm::Evaluator(ref evaluator) => {
-                    let needed = evaluator.operands_needed();
-                    num_operands = num_operands.checked_sub(needed).ok_or(NotEnoughOperand)?;
-                    num_operands += evaluator.operands_generated();
+                Arithm::Operand(operand) => stack.push(operand),
+                Arithm::Variable(ref var) => {
+                    let var = lookup(var)
+                        .ok_or_else(|| EvalErr::VariableNotFound(var.clone()))?;
+                    stack.push(var)
+                }
+                Arithm::Evaluator(evaluator) => {
+                    evaluator.evaluate(stack)
+                        .map_err(|err| EvalErr::EvalError(err))?
                 }
             }
+            inspect(arithm, stack);
         }
-        match num_operands {
-            0 => Err(NotEnoughOperand),
-            1 => Ok(()),
-            _ => Err(TooManyOperands),
-        }
+        Ok(())
     }
-}
 
-impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
-    fn compute_stack_max(expr: &[Arithm<T, V, E>]) -> usize {
-        expr.iter() .map(|arithm| {
+    /// Evaluates all the parts of the expression that only depend on literal
+    /// operands and on the `known` variables, returns the reduced expression.
+    ///
+    /// Variables not found in `known` are kept in place, evaluators failing
+    /// are kept too, the error will be returned by the final evaluation.
+    pub fn partial_evaluate<I, M, C>(&self, known: &C) -> Expression<T, V, E>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        let mut stack: Vec<Partial<T, V, E>> = Vec::with_capacity(self.max_stack);
+        for arithm in &self.expr {
             match *arithm {
-                Arithm::Operand(_) |
-                Arithm::Variable(_) => 1,
-                Arithm::Evaluator(ref op) => {
-                    op.operands_generated() as isize - op.operands_needed() as isize
+                Arithm::Operand(operand) => stack.push(Partial::Known(operand)),
+                Arithm::Variable(ref var) => {
+                    match known.get_variable_owned(var.clone().into()) {
+                        Some(value) => stack.push(Partial::Known(value)),
+                        None => {
+                            let var = Arithm::Variable(var.clone());
+                            stack.push(Partial::Unknown(vec![var], 1))
+                        }
+                    }
+                }
+                Arithm::Evaluator(evaluator) => {
+                    let needed = evaluator.operands_needed();
+                    let mut slots = 0;
+                    let mut start = stack.len();
+                    while slots < needed && start > 0 {
+                        start -= 1;
+                        slots += stack[start].slots();
+                    }
+                    let args = stack.split_off(start);
+                    let all_known = args.iter().all(|arg| match *arg {
+                        Partial::Known(_) => true,
+                        Partial::Unknown(..) => false,
+                    });
+
+                    if all_known && slots == needed {
+                        let mut eval_stack = Stack::with_capacity(needed);
+                        for arg in &args {
+                            if let Partial::Known(value) = *arg {
+                                eval_stack.push(value)
+                            }
+                        }
+                        if evaluator.evaluate(&mut eval_stack).is_ok() {
+                            let mut values = Vec::new();
+                            while let Some(value) = eval_stack.pop() {
+                                values.push(Partial::Known(value));
+                            }
+                            stack.extend(values.into_iter().rev());
+                            continue
+                        }
+                    }
+
+                    let mut tokens = Vec::new();
+                    for arg in args {
+                        arg.append_tokens(&mut tokens);
+                    }
+                    tokens.push(Arithm::Evaluator(evaluator));
+                    let slots = slots - needed + evaluator.operands_generated();
+                    stack.push(Partial::Unknown(tokens, slots))
                 }
             }
-        })
-        .fold((0, 0isize), |(max, acc_count), count| {
-            let acc = (acc_count + count) as usize;
-            if acc > max {
-                (acc, acc as isize)
-            } else {
-                (max, acc as isize)
-            }
-        }).0
+        }
+
+        let mut expr = Vec::new();
+        for partial in stack {
+            partial.append_tokens(&mut expr);
+        }
+        Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        }
+    }
+
+    /// Folds every sub-expression only made of literal operands
+    /// into a single operand, variables are kept in place.
+    ///
+    /// Evaluators that fail are not folded, the error will be returned
+    /// by the final evaluation.
+    pub fn constant_fold(self) -> Self {
+        self.partial_evaluate(&|_: V| None)
     }
 }
 
-impl<T, V, E> fmt::Display for Expression<T, V, E>
-    where T: fmt::Display,
-          V: fmt::Display,
-          E: fmt::Display + Evaluate<T>
+#[cfg(feature = "rayon")]
+impl<T, V, E> Expression<T, V, E>
+    where T: Copy + Send + Sync,
+          V: Clone + Send + Sync,
+          E: Evaluate<T> + Copy + Sync,
+          E::Err: Send
 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let len = self.expr.len();
-        for (i, arithm) in self.expr.iter().enumerate() {
-            match *arithm {
-                Arithm::Operand(ref operand) => operand.fmt(f)?,
-                Arithm::Variable(ref variable) => variable.fmt(f)?,
-                Arithm::Evaluator(ref evaluator) => evaluator.fmt(f)?,
+    /// Evaluate `RPN` expressions once for each of the `variable_sets` in parallel,
+    /// results are returned in the same order.
+    pub fn par_evaluate_batch<I, M, C>(&self, variable_sets: &[C])
+                                       -> Vec<Result<T, EvalErr<V, E::Err>>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + Sync
+    {
+        use rayon::prelude::*;
+        variable_sets.par_iter().map(|variables| self.evaluate_with_variables(variables)).collect()
+    }
+}
+
+/// An event yielded by the [`evaluate_steps()`] iterator.
+///
+/// [`evaluate_steps()`]: ../expression/struct.Expression.html#method.evaluate_steps
+#[derive(Debug, PartialEq, Eq)]
+pub enum EvalStep<T, V, E> {
+    /// A literal operand has been pushed on the stack.
+    PushedOperand(T),
+    /// The value of a variable has been pushed on the stack.
+    PushedVariable(T),
+    /// The variable has not been found, the iteration stops.
+    VariableNotFound(V),
+    /// An evaluator has been applied, the iteration stops on error.
+    EvaluatorResult(Result<(), E>),
+    /// All the tokens have been evaluated, this is the result.
+    Finished(T),
+}
+
+/// Iterator over the evaluation steps of an `Expression`,
+/// created by the [`evaluate_steps()`] method.
+///
+/// [`evaluate_steps()`]: ../expression/struct.Expression.html#method.evaluate_steps
+pub struct EvalSteps<'a, T: 'a, V: 'a, E: 'a + Evaluate<T>, I, M, C: 'a + ?Sized> {
+    tokens: slice::Iter<'a, Arithm<T, V, E>>,
+    variables: &'a C,
+    stack: Stack<T>,
+    finished: bool,
+    _marker: PhantomData<(I, M)>,
+}
+
+impl<'a, T, V, E: Evaluate<T>, I, M, C: ?Sized> EvalSteps<'a, T, V, E, I, M, C> {
+    /// Returns the current stack, from the bottom to the top.
+    pub fn current_stack(&self) -> &[T] {
+        self.stack.as_slice()
+    }
+}
+
+impl<'a, T, V, E, I, M, C> Iterator for EvalSteps<'a, T, V, E, I, M, C>
+    where T: Copy,
+          V: Clone + Into<I>,
+          E: Evaluate<T> + Copy,
+          C: GetVariableOwned<I, M, Output=T> + ?Sized
+{
+    type Item = EvalStep<T, V, E::Err>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None
+        }
+        let arithm = match self.tokens.next() {
+            Some(arithm) => arithm,
+            None => {
+                self.finished = true;
+                return Some(EvalStep::Finished(*self.stack.as_slice().last().unwrap()))
             }
-            if i != len - 1 {
-                f.write_str(" ")?
+        };
+        let step = match *arithm {
+            Arithm::Operand(operand) => {
+                self.stack.push(operand);
+                EvalStep::PushedOperand(operand)
+            }
+            Arithm::Variable(ref var) => {
+                match self.variables.get_variable_owned(var.clone().into()) {
+                    Some(value) => {
+                        self.stack.push(value);
+                        EvalStep::PushedVariable(value)
+                    }
+                    None => {
+                        self.finished = true;
+                        EvalStep::VariableNotFound(var.clone())
+                    }
+                }
+            }
+            Arithm::Evaluator(evaluator) => {
+                let result = evaluator.evaluate(&mut self.stack);
+                self.finished = result.is_err();
+                EvalStep::EvaluatorResult(result)
             }
+        };
+        Some(step)
+    }
+}
+
+/// A stack entry used by `partial_evaluate`, either an already evaluated value
+/// or the tokens producing the given number of values that cannot be evaluated yet.
+enum Partial<T, V, E: Evaluate<T>> {
+    Known(T),
+    Unknown(Vec<Arithm<T, V, E>>, usize),
+}
+
+impl<T, V, E: Evaluate<T>> Partial<T, V, E> {
+    fn slots(&self) -> usize {
+        match *self {
+            Partial::Known(_) => 1,
+            Partial::Unknown(_, slots) => slots,
         }
-        Ok(())
+    }
+
+    fn append_tokens(self, tokens: &mut Vec<Arithm<T, V, E>>) {
+        match self {
+            Partial::Known(value) => tokens.push(Arithm::Operand(value)),
+            Partial::Unknown(arithms, _) => tokens.extend(arithms),
+        }
+    }
+}
+
+impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
+    #[allow(clippy::type_complexity)]
+    pub fn from_iter<A, I>(iter: I)
+                           -> Result<Expression<T, V, E>,
+                                     ExprResult<<E as TryFromRef<A>>::Err,
+                                                <V as TryFromRef<A>>::Err,
+                                                <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>,
+              I: IntoIterator<Item=A>
+    {
+        Expression::from_token_stream(&mut TokenStream::new(iter))
+    }
+
+    /// Parses an expression whose tokens are separated by the given delimiter
+    /// instead of whitespaces, consecutive delimiters are skipped.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::FloatExpr;
+    ///
+    /// let expr = FloatExpr::<f32>::from_str_with_delimiter("3,4,,+", ',').unwrap();
+    /// assert_eq!(expr.evaluate(), Ok(7.0));
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn from_str_with_delimiter<'a>(s: &'a str, delimiter: char)
+                                       -> Result<Expression<T, V, E>,
+                                                 ExprResult<<E as TryFromRef<&'a str>>::Err,
+                                                            <V as TryFromRef<&'a str>>::Err,
+                                                            <T as TryFromRef<&'a str>>::Err>>
+        where T: TryFromRef<&'a str>,
+              V: TryFromRef<&'a str>,
+              E: TryFromRef<&'a str>
+    {
+        Expression::from_str_with_pattern(s, |c| c == delimiter)
+    }
+
+    /// Parses an expression whose tokens are separated by any character
+    /// matching the given predicate, empty tokens are skipped.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::FloatExpr;
+    ///
+    /// let expr = FloatExpr::<f32>::from_str_with_pattern("3;4\t+", |c| c == ';' || c == '\t');
+    /// assert_eq!(expr.unwrap().evaluate(), Ok(7.0));
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn from_str_with_pattern<'a, P>(s: &'a str, pattern: P)
+                                        -> Result<Expression<T, V, E>,
+                                                  ExprResult<<E as TryFromRef<&'a str>>::Err,
+                                                             <V as TryFromRef<&'a str>>::Err,
+                                                             <T as TryFromRef<&'a str>>::Err>>
+        where T: TryFromRef<&'a str>,
+              V: TryFromRef<&'a str>,
+              E: TryFromRef<&'a str>,
+              P: FnMut(char) -> bool
+    {
+        Expression::from_iter(s.split(pattern).filter(|token| !token.is_empty()))
+    }
+
+    /// Parses a whitespace separated expression, everything from the `comment_char`
+    /// (usually [`DEFAULT_COMMENT_CHAR`]) to the end of each line is ignored.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::FloatExpr;
+    /// use ripin::expression::DEFAULT_COMMENT_CHAR;
+    ///
+    /// let expr_str = "3 4 + # adds three and four\n2 *";
+    /// let expr = FloatExpr::<f32>::from_str_with_comments(expr_str, DEFAULT_COMMENT_CHAR).unwrap();
+    /// assert_eq!(expr.evaluate(), Ok(14.0));
+    /// # }
+    /// ```
+    ///
+    /// [`DEFAULT_COMMENT_CHAR`]: constant.DEFAULT_COMMENT_CHAR.html
+    #[allow(clippy::type_complexity)]
+    pub fn from_str_with_comments<'a>(s: &'a str, comment_char: char)
+                                      -> Result<Expression<T, V, E>,
+                                                ExprResult<<E as TryFromRef<&'a str>>::Err,
+                                                           <V as TryFromRef<&'a str>>::Err,
+                                                           <T as TryFromRef<&'a str>>::Err>>
+        where T: TryFromRef<&'a str>,
+              V: TryFromRef<&'a str>,
+              E: TryFromRef<&'a str>
+    {
+        let tokens = s.lines().flat_map(|line| {
+            line.split(comment_char).next().unwrap_or("").split_whitespace()
+        });
+        Expression::from_iter(tokens)
+    }
+
+    /// Parses all the remaining tokens of the stream, error positions are
+    /// the ones of the stream, even if some tokens were already consumed.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::FloatExpr;
+    /// use ripin::expression::TokenStream;
+    ///
+    /// let mut tokens = TokenStream::split_whitespace("rpn: 3 4 +");
+    /// if tokens.peek() == Some("rpn:") {
+    ///     tokens.next();
+    /// }
+    /// let expr = FloatExpr::<f32>::from_token_stream(&mut tokens).unwrap();
+    /// assert_eq!(expr.evaluate(), Ok(7.0));
+    /// # }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn from_token_stream<A, I>(tokens: &mut TokenStream<I>)
+                                   -> Result<Expression<T, V, E>,
+                                             ExprResult<<E as TryFromRef<A>>::Err,
+                                                        <V as TryFromRef<A>>::Err,
+                                                        <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>,
+              I: Iterator<Item=A>
+    {
+        // `tokens.position()` would resolve to `Iterator::position` of `&mut TokenStream`
+        let offset = TokenStream::position(tokens);
+        let expr = Expression::parse_tokens(offset, tokens.by_ref())?;
+        Expression::check_validity(&expr).map_err(ExprResult::OperandErr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        })
+    }
+
+    /// Parses a whitespace separated expression from a reader, one line at a time,
+    /// the whole input is never loaded in memory.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use std::io::Cursor;
+    /// use ripin::evaluate::FloatExpr;
+    ///
+    /// let reader = Cursor::new("3 4 +\n2 *\n");
+    /// let expr = FloatExpr::<f32>::from_reader(reader).unwrap();
+    /// assert_eq!(expr.evaluate(), Ok(14.0));
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Expression<T, V, E>, ReadExprError>
+        where T: for<'a> TryFromRef<&'a str>,
+              V: for<'a> TryFromRef<&'a str>,
+              E: for<'a> TryFromRef<&'a str>
+    {
+        let mut parser = ExpressionParser::new();
+        for line in reader.lines() {
+            let line = line.map_err(ReadExprError::Io)?;
+            for token in line.split_whitespace() {
+                parser.push_token(token).map_err(|err| {
+                    match err {
+                        ExprResult::OperandErr(err) => ParseExprError::OperandErr(err),
+                        ExprResult::InvalidToken { .. } => {
+                            ParseExprError::InvalidToken(token.to_string())
+                        }
+                    }
+                }).map_err(ReadExprError::Parse)?;
+            }
+        }
+        parser.finalize().map_err(|err| ReadExprError::Parse(ParseExprError::OperandErr(err)))
+    }
+
+    /// Parses an expression from a JSON array of string tokens, the format
+    /// produced by [`to_json()`] (cf. `["3","4","+"]`), without requiring `serde`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::FloatExpr;
+    ///
+    /// let expr = FloatExpr::<f32>::from_json(r#"["3", "4", "+"]"#).unwrap();
+    /// assert_eq!(expr.evaluate(), Ok(7.0));
+    /// # }
+    /// ```
+    ///
+    /// [`to_json()`]: #method.to_json
+    pub fn from_json(json: &str) -> Result<Expression<T, V, E>, JsonParseError>
+        where T: for<'a> TryFromRef<&'a str>,
+              V: for<'a> TryFromRef<&'a str>,
+              E: for<'a> TryFromRef<&'a str>
+    {
+        let tokens = parse_json_strings(json).map_err(JsonParseError::Malformed)?;
+        Expression::from_iter(tokens.iter().map(String::as_str)).map_err(|err| {
+            match err {
+                ExprResult::OperandErr(err) => ParseExprError::OperandErr(err),
+                ExprResult::InvalidToken { position, .. } => {
+                    ParseExprError::InvalidToken(tokens[position].clone())
+                }
+            }
+        }).map_err(JsonParseError::Parse)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_tokens<A, I>(offset: usize, iter: I)
+                          -> Result<Vec<Arithm<T, V, E>>,
+                                    ExprResult<<E as TryFromRef<A>>::Err,
+                                               <V as TryFromRef<A>>::Err,
+                                               <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>,
+              I: IntoIterator<Item=A>
+    {
+        iter.into_iter().enumerate().map(|(position, token)| {
+            Expression::parse_token(offset + position, &token)
+        }).collect()
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_token<A>(position: usize, token: &A)
+                      -> Result<Arithm<T, V, E>,
+                                ExprResult<<E as TryFromRef<A>>::Err,
+                                           <V as TryFromRef<A>>::Err,
+                                           <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>
+    {
+        match TryIntoRef::<E>::try_into_ref(token) {
+            Ok(eval) => Ok(Arithm::Evaluator(eval)),
+            Err(eval_err) => {
+                match TryIntoRef::<V>::try_into_ref(token) {
+                    Ok(var) => Ok(Arithm::Variable(var)),
+                    Err(var_err) => {
+                        match TryIntoRef::<T>::try_into_ref(token) {
+                            Ok(op) => Ok(Arithm::Operand(op)),
+                            Err(op_err) => Err(ExprResult::InvalidToken {
+                                position,
+                                evaluator: eval_err,
+                                variable: var_err,
+                                operand: op_err,
+                            })
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the number of tokens (operands, variables and evaluators)
+    /// in the expression.
+    pub fn len(&self) -> usize {
+        self.expr.len()
+    }
+
+    /// Returns `true` if the expression contains no token,
+    /// a valid `Expression` can never be empty.
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_empty()
+    }
+
+    /// Returns the token at the given position,
+    /// or `None` if the index is out of bounds.
+    pub fn token_at(&self, idx: usize) -> Option<&Arithm<T, V, E>> {
+        self.expr.get(idx)
+    }
+
+    /// Concatenates `self` and `other` then applies `evaluator` on both results,
+    /// it is the `RPN` equivalent of `(self) (other) evaluator`.
+    pub fn combine(self, other: Expression<T, V, E>, evaluator: E)
+                   -> Result<Expression<T, V, E>, OperandErr>
+    {
+        self.cat(other).apply(evaluator)
+    }
+
+    /// Concatenates the tokens of `self` and `other`, the returned [`CatExpression`]
+    /// leaves both results on the stack until an evaluator is applied to it.
+    ///
+    /// [`CatExpression`]: ../expression/struct.CatExpression.html
+    pub fn cat(self, other: Expression<T, V, E>) -> CatExpression<T, V, E> {
+        let mut expr = self.expr;
+        expr.extend(other.expr);
+        CatExpression { expr }
+    }
+
+    /// Returns an expression equivalent to evaluating `self` `n` times in sequence,
+    /// the result of each evaluation being the first operand of the next one.
+    ///
+    /// The first token of `self` is kept as the initial operand and
+    /// the following tokens are appended `n` times (cf. `x 2 *` gives `x 2 * 2 * 2 *`).
+    pub fn repeat(&self, n: usize) -> Expression<T, V, E>
+        where T: Copy,
+              V: Copy,
+              E: Copy
+    {
+        let (first, rest) = self.expr.split_first().expect("expressions are never empty");
+        let mut expr = Vec::with_capacity(1 + rest.len() * n);
+        expr.push(*first);
+        for _ in 0..n {
+            expr.extend_from_slice(rest);
+        }
+        Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        }
+    }
+
+    /// Replaces every occurrence of the `target` variable
+    /// by the tokens of the `replacement` expression.
+    pub fn substitute_variable(self, target: V, replacement: Expression<T, V, E>)
+                               -> Result<Expression<T, V, E>, OperandErr>
+        where T: Clone,
+              V: PartialEq + Clone,
+              E: Clone
+    {
+        let mut expr = Vec::with_capacity(self.expr.len());
+        for arithm in self.expr {
+            match arithm {
+                Arithm::Variable(ref var) if *var == target => {
+                    expr.extend(replacement.expr.iter().cloned())
+                }
+                arithm => expr.push(arithm),
+            }
+        }
+        Expression::check_validity(&expr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        })
+    }
+
+    /// Rewrites the expression with simple algebraic identities until none apply:
+    /// `x 0 +`, `0 x +`, `x 0 -`, `x 1 *`, `1 x *` and `x 1 /` give `x`,
+    /// `x 0 *`, `0 x *` and `x x -` give `0`.
+    ///
+    /// Evaluators are recognized by comparing them with the [`ArithmeticOperators`],
+    /// the identities do not take care of `NaN`, infinite values
+    /// or evaluation errors `x` could produce.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::VariableFloatExprNamed;
+    ///
+    /// let tokens = "$x 0 + 1 * $y $y - +".split_whitespace();
+    /// let expr = VariableFloatExprNamed::<f32>::from_iter(tokens).unwrap();
+    /// let simplified = VariableFloatExprNamed::<f32>::from_iter(Some("$x")).unwrap();
+    /// assert_eq!(expr.symbolic_simplify(), simplified);
+    /// # }
+    /// ```
+    ///
+    /// [`ArithmeticOperators`]: ../evaluate/trait.ArithmeticOperators.html
+    pub fn symbolic_simplify(self) -> Expression<T, V, E>
+        where T: PartialEq + Zero + One,
+              V: PartialEq,
+              E: PartialEq + ArithmeticOperators
+    {
+        let mut expr = self.expr;
+        while Expression::simplify_step(&mut expr) {}
+        Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        }
+    }
+
+    fn simplify_step(expr: &mut Vec<Arithm<T, V, E>>) -> bool
+        where T: PartialEq + Zero + One,
+              V: PartialEq,
+              E: PartialEq + ArithmeticOperators
+    {
+        let is_operand = |tokens: &[Arithm<T, V, E>], value: &T| match tokens {
+            [Arithm::Operand(operand)] => operand == value,
+            _ => false,
+        };
+        let (zero, one) = (T::zero(), T::one());
+        let (add, sub, mul, div) = (E::add(), E::sub(), E::mul(), E::div());
+
+        for i in 1..expr.len() {
+            let (is_add, is_sub, is_mul, is_div) = match expr[i] {
+                Arithm::Evaluator(ref e) if e.operands_needed() == 2
+                                         && e.operands_generated() == 1 => {
+                    (*e == add, *e == sub, *e == mul, *e == div)
+                }
+                _ => continue,
+            };
+            let rhs = match Expression::operand_start(expr, i - 1) {
+                Some(start) if start > 0 => start,
+                _ => continue,
+            };
+            let lhs = match Expression::operand_start(expr, rhs - 1) {
+                Some(start) => start,
+                None => continue,
+            };
+            let (left, right) = (&expr[lhs..rhs], &expr[rhs..i]);
+
+            let keep_left = ((is_add || is_sub) && is_operand(right, &zero))
+                         || ((is_mul || is_div) && is_operand(right, &one));
+            let keep_right = (is_add && is_operand(left, &zero))
+                          || (is_mul && is_operand(left, &one));
+            let is_zero = (is_mul && (is_operand(left, &zero) || is_operand(right, &zero)))
+                       || (is_sub && left == right);
+
+            if keep_left {
+                expr.drain(rhs..=i);
+            } else if keep_right {
+                expr.remove(i);
+                expr.drain(lhs..rhs);
+            } else if is_zero {
+                expr.splice(lhs..=i, Some(Arithm::Operand(T::zero())));
+            } else {
+                continue
+            }
+            return true
+        }
+        false
+    }
+
+    /// Returns the position of the first token of the operand whose last token is at `end`,
+    /// or `None` if it does not generate exactly one value.
+    fn operand_start(expr: &[Arithm<T, V, E>], end: usize) -> Option<usize> {
+        let mut needed = 1;
+        for (i, arithm) in expr[..=end].iter().enumerate().rev() {
+            let (consumed, generated) = match *arithm {
+                Arithm::Operand(_) | Arithm::Variable(_) => (0, 1),
+                Arithm::Evaluator(ref e) => (e.operands_needed(), e.operands_generated()),
+            };
+            if generated > needed {
+                return None
+            }
+            needed = needed - generated + consumed;
+            if needed == 0 {
+                return Some(i)
+            }
+        }
+        None
+    }
+
+    /// Applies the rewrite `rules` to the expression in a single left to right pass,
+    /// tokens are appended one by one and the first rule whose pattern matches
+    /// the end of the tokens already appended replaces them.
+    ///
+    /// A [`WildcardVar`] of a pattern matches any sub-expression, a wildcard used
+    /// multiple times must match equal sub-expressions, and is replaced in the replacement
+    /// by the sub-expression it is bound to. Other variables only match themselves.
+    /// The tokens of the replacements and the bound sub-expressions are cloned,
+    /// hence the `Clone` bounds.
+    ///
+    /// Rules whose replacement doesn't leave the stack as their pattern does,
+    /// or uses a wildcard the pattern doesn't bind, are ignored. A rewrite that would
+    /// make the expression need more operands than available is not applied,
+    /// the result is always a valid expression.
+    ///
+    /// ```
+    /// # #[cfg(feature = "float-evaluator")] {
+    /// use ripin::evaluate::VariableFloatExprNamed;
+    /// use ripin::expression::RewriteRule;
+    ///
+    /// let parse = |expr: &str| {
+    ///     VariableFloatExprNamed::<f32>::from_iter(expr.split_whitespace()).unwrap()
+    /// };
+    /// let rules = [RewriteRule::from_expressions(parse("$a $b + $c *"),
+    ///                                            parse("$a $c * $b $c * +"))];
+    /// let expr = parse("2 $x + 4 *").rewrite(&rules);
+    /// assert_eq!(expr, parse("2 4 * $x 4 * +"));
+    /// # }
+    /// ```
+    ///
+    /// [`WildcardVar`]: ../expression/struct.WildcardVar.html
+    pub fn rewrite(self, rules: &[RewriteRule<T, V, E>]) -> Expression<T, V, E>
+        where T: PartialEq + Clone,
+              V: PartialEq + Clone,
+              E: PartialEq + Clone
+    {
+        let rules: Vec<_> = rules.iter().filter(|rule| {
+            let (needed, generated) = Expression::stack_effect(&rule.pattern);
+            let (rep_needed, rep_generated) = Expression::stack_effect(&rule.replacement);
+            let bound = |wildcard: &WildcardVar| rule.pattern.iter().any(|arithm| {
+                arithm.as_variable() == Some(&RuleVar::Wildcard(*wildcard))
+            });
+            let wildcards_bound = rule.replacement.iter().all(|arithm| {
+                match *arithm {
+                    Arithm::Variable(RuleVar::Wildcard(ref wildcard)) => bound(wildcard),
+                    _ => true,
+                }
+            });
+            wildcards_bound && rep_needed <= needed
+                && generated + rep_needed == rep_generated + needed
+        }).collect();
+
+        let mut expr = Vec::with_capacity(self.expr.len());
+        for arithm in self.expr {
+            expr.push(arithm);
+            for rule in &rules {
+                if let Some((start, bindings)) = Expression::match_end(&expr, &rule.pattern) {
+                    let mut rewritten = expr[..start].to_vec();
+                    for arithm in &rule.replacement {
+                        rewritten.push(match *arithm {
+                            Arithm::Operand(ref operand) => Arithm::Operand(operand.clone()),
+                            Arithm::Evaluator(ref evaluator) => {
+                                Arithm::Evaluator(evaluator.clone())
+                            }
+                            Arithm::Variable(RuleVar::Var(ref var)) => {
+                                Arithm::Variable(var.clone())
+                            }
+                            Arithm::Variable(RuleVar::Wildcard(wildcard)) => {
+                                let bound = bindings.iter().find(|&&(w, _)| w == wildcard);
+                                let range = bound.map(|(_, range)| range.clone())
+                                    .expect("wildcards of the replacement are bound");
+                                rewritten.extend_from_slice(&expr[range]);
+                                continue
+                            }
+                        });
+                    }
+                    if Expression::count_results(&rewritten).is_ok() {
+                        expr = rewritten;
+                        break
+                    }
+                }
+            }
+        }
+
+        Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        }
+    }
+
+    /// Returns the position where the `pattern` starts matching the end of `expr`
+    /// along with the tokens range bound to each wildcard.
+    #[allow(clippy::type_complexity)]
+    fn match_end(expr: &[Arithm<T, V, E>], pattern: &[Arithm<T, RuleVar<V>, E>])
+                 -> Option<(usize, Vec<(WildcardVar, Range<usize>)>)>
+        where T: PartialEq,
+              V: PartialEq,
+              E: PartialEq
+    {
+        let mut end = expr.len();
+        let mut bindings: Vec<(WildcardVar, Range<usize>)> = Vec::new();
+        for arithm in pattern.iter().rev() {
+            if end == 0 {
+                return None
+            }
+            match (arithm, &expr[end - 1]) {
+                (&Arithm::Variable(RuleVar::Wildcard(wildcard)), _) => {
+                    let start = Expression::operand_start(expr, end - 1)?;
+                    let bound = bindings.iter().find(|&&(w, _)| w == wildcard);
+                    if let Some((_, range)) = bound {
+                        if expr[range.clone()] != expr[start..end] {
+                            return None
+                        }
+                    }
+                    bindings.push((wildcard, start..end));
+                    end = start;
+                }
+                (Arithm::Variable(RuleVar::Var(a)), Arithm::Variable(b)) if a == b => end -= 1,
+                (Arithm::Operand(a), Arithm::Operand(b)) if a == b => end -= 1,
+                (Arithm::Evaluator(a), Arithm::Evaluator(b)) if a == b => end -= 1,
+                _ => return None,
+            }
+        }
+        Some((end, bindings))
+    }
+
+    /// Returns the number of operands the tokens need and generate,
+    /// variables count as operands.
+    fn stack_effect(tokens: &[Arithm<T, V, E>]) -> (usize, usize) {
+        let (mut depth, mut lowest) = (0isize, 0isize);
+        for arithm in tokens {
+            if let Arithm::Evaluator(ref evaluator) = *arithm {
+                depth -= evaluator.operands_needed() as isize;
+                lowest = lowest.min(depth);
+                depth += evaluator.operands_generated() as isize;
+            } else {
+                depth += 1;
+            }
+        }
+        ((-lowest) as usize, (depth - lowest) as usize)
+    }
+
+    /// Transforms every `Evaluator` with `f`, operands and variables are kept intact.
+    ///
+    /// Returns an error if the transformed evaluators don't need and generate
+    /// a number of operands that keeps the expression valid.
+    pub fn map_evaluators<E2, F>(self, f: F) -> Result<Expression<T, V, E2>, OperandErr>
+        where E2: Evaluate<T>,
+              F: Fn(E) -> E2
+    {
+        let expr: Vec<_> = self.expr.into_iter().map(|arithm| {
+            match arithm {
+                Arithm::Operand(operand) => Arithm::Operand(operand),
+                Arithm::Variable(var) => Arithm::Variable(var),
+                Arithm::Evaluator(evaluator) => Arithm::Evaluator(f(evaluator)),
+            }
+        }).collect();
+        Expression::check_validity(&expr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        })
+    }
+
+    /// Transforms every literal `Operand` with `f` and every `Evaluator`
+    /// with `eval_convert`, variables are kept intact.
+    ///
+    /// Returns an error if the converted evaluators don't need and generate
+    /// a number of operands that keeps the expression valid.
+    pub fn map_operands<U, E2, F, G>(self, f: F, eval_convert: G)
+                                     -> Result<Expression<U, V, E2>, OperandErr>
+        where E2: Evaluate<U>,
+              F: Fn(T) -> U,
+              G: Fn(E) -> E2
+    {
+        let expr: Vec<_> = self.expr.into_iter().map(|arithm| {
+            match arithm {
+                Arithm::Operand(operand) => Arithm::Operand(f(operand)),
+                Arithm::Variable(var) => Arithm::Variable(var),
+                Arithm::Evaluator(evaluator) => Arithm::Evaluator(eval_convert(evaluator)),
+            }
+        }).collect();
+        Expression::check_validity(&expr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        })
+    }
+
+    /// Returns the maximum number of values on the stack during the evaluation,
+    /// computed at construction, useful to preallocate a [`Stack`].
+    ///
+    /// [`Stack`]: ../struct.Stack.html
+    pub fn max_stack_depth(&self) -> usize {
+        self.max_stack
+    }
+
+    /// Returns `true` if both expressions are made of the same tokens in the same order,
+    /// without evaluating them: `3 4 +` is not structurally equal to `4 3 +`.
+    pub fn structural_eq(&self, other: &Self) -> bool
+        where T: PartialEq,
+              V: PartialEq,
+              E: PartialEq
+    {
+        self.max_stack == other.max_stack && self.expr == other.expr
+    }
+
+    /// Returns the depth of the stack after each token,
+    /// computed without evaluating the expression.
+    pub fn depth_profile(&self) -> Vec<usize> {
+        self.expr.iter().scan(0, |depth, arithm| {
+            match *arithm {
+                Arithm::Operand(_) |
+                Arithm::Variable(_) => *depth += 1,
+                Arithm::Evaluator(ref evaluator) => {
+                    *depth = *depth - evaluator.operands_needed() + evaluator.operands_generated();
+                }
+            }
+            Some(*depth)
+        }).collect()
+    }
+
+    /// Returns the [`StackStats`] of the expression, computed
+    /// from the operands needed and generated by each token.
+    ///
+    /// ```
+    /// # #[cfg(feature = "int-evaluator")] {
+    /// use ripin::evaluate::IntExpr;
+    ///
+    /// let expr = IntExpr::<i32>::from_iter("1 2 3 + +".split_whitespace()).unwrap();
+    /// let stats = expr.stack_statistics();
+    /// assert_eq!((stats.min_depth, stats.max_depth), (1, 3));
+    /// # }
+    /// ```
+    ///
+    /// [`StackStats`]: struct.StackStats.html
+    pub fn stack_statistics(&self) -> StackStats {
+        let depths = self.depth_profile();
+        let (total_pushes, total_pops) = self.expr.iter().fold((0, 0), |(pushes, pops), arithm| {
+            match *arithm {
+                Arithm::Operand(_) | Arithm::Variable(_) => (pushes + 1, pops),
+                Arithm::Evaluator(ref evaluator) => {
+                    (pushes + evaluator.operands_generated(), pops + evaluator.operands_needed())
+                }
+            }
+        });
+        StackStats {
+            min_depth: depths.iter().cloned().min().unwrap_or(0),
+            max_depth: self.max_stack,
+            total_pushes,
+            total_pops,
+        }
+    }
+
+    /// Returns an iterator over the tokens of the expression.
+    pub fn iter(&self) -> slice::Iter<'_, Arithm<T, V, E>> {
+        self.expr.iter()
+    }
+
+    /// Folds every token of the expression into an accumulator,
+    /// like [`Iterator::fold()`] does.
+    ///
+    /// ```
+    /// # #[cfg(feature = "int-evaluator")] {
+    /// use ripin::evaluate::IntExpr;
+    ///
+    /// let expr = IntExpr::<i32>::from_iter("3 4 5 + *".split_whitespace()).unwrap();
+    /// let operands = expr.fold_tokens(0, |count, arithm| {
+    ///     if arithm.is_operand() { count + 1 } else { count }
+    /// });
+    /// assert_eq!(operands, 3);
+    /// # }
+    /// ```
+    ///
+    /// [`Iterator::fold()`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.fold
+    pub fn fold_tokens<B, F>(&self, init: B, f: F) -> B
+        where F: FnMut(B, &Arithm<T, V, E>) -> B
+    {
+        self.expr.iter().fold(init, f)
+    }
+
+    /// Returns an iterator over the variables referenced by the expression,
+    /// in order of appearance.
+    pub fn variables(&self) -> impl Iterator<Item=&V> {
+        self.expr.iter().filter_map(|arithm| {
+            match *arithm {
+                Arithm::Variable(ref var) => Some(var),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns `true` if the expression doesn't reference any variable,
+    /// it can then be evaluated without providing variables.
+    pub fn is_constant(&self) -> bool {
+        self.variables().next().is_none()
+    }
+
+    /// Returns the number of variable references in the expression,
+    /// a variable referenced multiple times is counted each time.
+    pub fn variable_count(&self) -> usize {
+        self.variables().count()
+    }
+
+    /// Returns the number of literal operands in the expression.
+    pub fn operand_count(&self) -> usize {
+        self.operands().count()
+    }
+
+    /// Returns the number of evaluators in the expression.
+    pub fn evaluator_count(&self) -> usize {
+        self.evaluators().count()
+    }
+
+    /// Returns the variables referenced by the expression without duplicates,
+    /// in order of first appearance.
+    pub fn referenced_variables(&self) -> Vec<&V>
+        where V: PartialEq
+    {
+        let mut variables = Vec::new();
+        for variable in self.variables() {
+            if !variables.contains(&variable) {
+                variables.push(variable)
+            }
+        }
+        variables
+    }
+
+    /// Returns the number of distinct variables referenced by the expression.
+    pub fn referenced_variable_count(&self) -> usize
+        where V: PartialEq
+    {
+        self.referenced_variables().len()
+    }
+
+    /// Checks that every variable index of the expression is lower than `count`,
+    /// returns the first out of range variable otherwise.
+    pub fn validate_variable_indices(&self, count: usize) -> Result<(), VariableIndexError>
+        where V: Into<usize> + Copy
+    {
+        match self.variables().map(|&var| var.into()).find(|&index| index >= count) {
+            Some(index) => Err(VariableIndexError { index, max: count.checked_sub(1) }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns an iterator over the literal operands of the expression,
+    /// in order of appearance.
+    pub fn operands(&self) -> impl Iterator<Item=&T> {
+        self.expr.iter().filter_map(|arithm| {
+            match *arithm {
+                Arithm::Operand(ref operand) => Some(operand),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns an iterator over the evaluators of the expression,
+    /// in order of appearance.
+    pub fn evaluators(&self) -> impl Iterator<Item=&E> {
+        self.expr.iter().filter_map(|arithm| {
+            match *arithm {
+                Arithm::Evaluator(ref evaluator) => Some(evaluator),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns `true` if the expression contains the given evaluator.
+    pub fn contains_evaluator(&self, eval: &E) -> bool
+        where E: PartialEq
+    {
+        self.evaluators().any(|evaluator| evaluator == eval)
+    }
+
+    /// Returns `true` if the expression contains the given literal operand.
+    pub fn contains_operand(&self, op: &T) -> bool
+        where T: PartialEq
+    {
+        self.operands().any(|operand| operand == op)
+    }
+
+    /// Returns `true` if the expression references the given variable.
+    pub fn contains_variable(&self, var: &V) -> bool
+        where V: PartialEq
+    {
+        self.variables().any(|variable| variable == var)
+    }
+
+    /// Counts the occurrences of each evaluator of the expression,
+    /// grouped by the key returned by `key_fn`.
+    pub fn count_operations_by<K, F>(&self, key_fn: F) -> BTreeMap<K, usize>
+        where K: Ord,
+              F: Fn(&E) -> K
+    {
+        let mut counts = BTreeMap::new();
+        for evaluator in self.evaluators() {
+            *counts.entry(key_fn(evaluator)).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl<'a, T, V, E: Evaluate<T>> IntoIterator for &'a Expression<T, V, E> {
+    type Item = &'a Arithm<T, V, E>;
+    type IntoIter = slice::Iter<'a, Arithm<T, V, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, V, E: Evaluate<T>> IntoIterator for Expression<T, V, E> {
+    type Item = Arithm<T, V, E>;
+    type IntoIter = vec::IntoIter<Arithm<T, V, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.expr.into_iter()
+    }
+}
+
+/// Used to specify the error during the conversion.
+#[derive(Debug, PartialEq)]
+pub enum ExprResult<A, B, C> {
+    OperandErr(OperandErr),
+    InvalidToken {
+        /// The index of the invalid token in the input iterator.
+        position: usize,
+        evaluator: A,
+        variable: B,
+        operand: C,
+    },
+}
+
+/// Used to specify the error when parsing an expression from a [`str`],
+/// the invalid token is owned to be independent of the parsed string.
+///
+/// [`str`]: https://doc.rust-lang.org/std/str/index.html
+#[derive(Debug, PartialEq)]
+pub enum ParseExprError {
+    OperandErr(OperandErr),
+    InvalidToken(String),
+}
+
+impl fmt::Display for ParseExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseExprError::OperandErr(ref err) => err.fmt(f),
+            ParseExprError::InvalidToken(ref token) => write!(f, "invalid token: '{}'", token),
+        }
+    }
+}
+
+impl Error for ParseExprError {}
+
+/// Used to specify the error when reading an expression from an [`io::BufRead`].
+///
+/// [`io::BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadExprError {
+    Io(io::Error),
+    Parse(ParseExprError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadExprError::Io(ref err) => err.fmt(f),
+            ReadExprError::Parse(ref err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ReadExprError {}
+
+/// Used to specify the error when parsing an expression from JSON.
+#[derive(Debug, PartialEq)]
+pub enum JsonParseError {
+    /// The input is not a JSON array of strings,
+    /// the byte offset where it stops being valid is given.
+    Malformed(usize),
+    Parse(ParseExprError),
+}
+
+impl fmt::Display for JsonParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonParseError::Malformed(offset) => {
+                write!(f, "expected a JSON array of strings, invalid at byte {}", offset)
+            }
+            JsonParseError::Parse(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for JsonParseError {}
+
+/// Parses a JSON array of strings, returns the byte offset of the first error.
+fn parse_json_strings(json: &str) -> Result<Vec<String>, usize> {
+    let bytes = json.as_bytes();
+    let skip_whitespaces = |mut i: usize| {
+        while i < bytes.len() && matches!(bytes[i], b' ' | b'\t' | b'\n' | b'\r') {
+            i += 1;
+        }
+        i
+    };
+
+    let mut tokens = Vec::new();
+    let mut i = skip_whitespaces(0);
+    if bytes.get(i) != Some(&b'[') {
+        return Err(i)
+    }
+    i = skip_whitespaces(i + 1);
+    if bytes.get(i) == Some(&b']') {
+        i += 1;
+    } else {
+        loop {
+            if bytes.get(i) != Some(&b'"') {
+                return Err(i)
+            }
+            let (token, end) = parse_json_string(json, i + 1)?;
+            tokens.push(token);
+            i = skip_whitespaces(end);
+            match bytes.get(i) {
+                Some(&b',') => i = skip_whitespaces(i + 1),
+                Some(&b']') => { i += 1; break }
+                _ => return Err(i),
+            }
+        }
+    }
+
+    let end = skip_whitespaces(i);
+    if end != bytes.len() {
+        return Err(end)
+    }
+    Ok(tokens)
+}
+
+/// Parses the content of a JSON string starting after its opening quote,
+/// returns the unescaped string and the offset following its closing quote.
+fn parse_json_string(json: &str, start: usize) -> Result<(String, usize), usize> {
+    let hex_unit = |i: usize| {
+        json.get(i..i + 4)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+            .ok_or(i)
+    };
+
+    let mut string = String::new();
+    let mut chars = json[start..].char_indices().map(|(i, c)| (start + i, c));
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((string, i + 1)),
+            '\\' => {
+                let (i, escaped) = chars.next().ok_or(json.len())?;
+                let unescaped = match escaped {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let mut units = vec![hex_unit(i + 1)?];
+                        let mut end = i + 5;
+                        if (0xD800..0xDC00).contains(&units[0]) && json[end..].starts_with("\\u") {
+                            units.push(hex_unit(end + 2)?);
+                            end += 6;
+                        }
+                        let mut decoded = char::decode_utf16(units.iter().cloned());
+                        let c = match decoded.next() {
+                            Some(Ok(c)) if decoded.next().is_none() => c,
+                            _ => return Err(i - 1),
+                        };
+                        chars.nth(end - i - 2);
+                        c
+                    }
+                    _ => return Err(i),
+                };
+                string.push(unescaped);
+            }
+            c if (c as u32) < 0x20 => return Err(i),
+            c => string.push(c),
+        }
+    }
+    Err(json.len())
+}
+
+impl<A, B, C> fmt::Display for ExprResult<A, B, C>
+    where A: fmt::Display,
+          B: fmt::Display,
+          C: fmt::Display
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExprResult::OperandErr(ref err) => err.fmt(f),
+            ExprResult::InvalidToken { position, ref evaluator, ref variable, ref operand } => {
+                write!(f, "invalid token at position {} (evaluator: {}, variable: {}, operand: {})",
+                       position, evaluator, variable, operand)
+            }
+        }
+    }
+}
+
+impl<A: Error, B: Error, C: Error> Error for ExprResult<A, B, C> {}
+
+/// Used to specify an error related to wrong number of operands in expression.
+#[derive(Debug, PartialEq)]
+pub enum OperandErr {
+    /// More than one value would be left on the stack,
+    /// `found` is the number of values left.
+    TooManyOperands { found: usize },
+    /// An evaluator `needed` more operands than `available` on the stack,
+    /// or the expression doesn't produce any value.
+    NotEnoughOperand { needed: usize, available: usize },
+}
+
+impl fmt::Display for OperandErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OperandErr::TooManyOperands { found } => {
+                write!(f, "too many operands left on the stack ({} found)", found)
+            }
+            OperandErr::NotEnoughOperand { needed, available } => {
+                write!(f, "not enough operands on the stack ({} needed, {} available)",
+                       needed, available)
+            }
+        }
+    }
+}
+
+impl Error for OperandErr {}
+
+/// Used to specify a variable `index` greater than the `max` index allowed,
+/// `max` is `None` when no variable is allowed at all.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VariableIndexError {
+    pub index: usize,
+    pub max: Option<usize>,
+}
+
+impl fmt::Display for VariableIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) => write!(f, "variable index {} out of range (max {})", self.index, max),
+            None => write!(f, "variable index {} out of range (no variable allowed)", self.index),
+        }
+    }
+}
+
+impl Error for VariableIndexError {}
+
+/// Statistics about the stack usage of an expression,
+/// computed without evaluating it (cf. [`Expression::stack_statistics()`]).
+///
+/// [`Expression::stack_statistics()`]: struct.Expression.html#method.stack_statistics
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct StackStats {
+    /// The minimum number of values on the stack after each token.
+    pub min_depth: usize,
+    /// The maximum number of values on the stack after each token.
+    pub max_depth: usize,
+    /// The number of values pushed by operands, variables and evaluators.
+    pub total_pushes: usize,
+    /// The number of values popped by evaluators.
+    pub total_pops: usize,
+}
+
+impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
+    fn check_validity(expr: &[Arithm<T, V, E>]) -> Result<(), OperandErr> {
+        use self::OperandErr::*;
+        match Expression::count_results(expr)? {
+            0 => Err(NotEnoughOperand { needed: 1, available: 0 }),
+            1 => Ok(()),
+            found => Err(TooManyOperands { found }),
+        }
+    }
+
+    fn count_results(expr: &[Arithm<T, V, E>]) -> Result<usize, OperandErr> {
+        // TODO https://doc.rust-lang.org/1.2.0/std/result/fn.fold.html
+        use self::OperandErr::*;
+        let mut num_operands: usize = 0;
+        for arithm in expr {
+            match *arithm {
+                Arithm::Operand(_) |
+                Arithm::Variable(_) => num_operands += 1,
+                Arithm::Evaluator(ref evaluator) => {
+                    let needed = evaluator.operands_needed();
+                    num_operands = num_operands.checked_sub(needed)
+                        .ok_or(NotEnoughOperand { needed, available: num_operands })?;
+                    num_operands += evaluator.operands_generated();
+                }
+            }
+        }
+        Ok(num_operands)
+    }
+}
+
+/// A rule used by [`Expression::rewrite()`] to replace the tokens matching
+/// the `pattern` by the `replacement` ones.
+///
+/// [`Expression::rewrite()`]: ../expression/struct.Expression.html#method.rewrite
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewriteRule<T, V, E: Evaluate<T>> {
+    pub pattern: Vec<Arithm<T, RuleVar<V>, E>>,
+    pub replacement: Vec<Arithm<T, RuleVar<V>, E>>,
+}
+
+impl<T, V, E: Evaluate<T>> RewriteRule<T, V, E> {
+    /// Creates a rule from the tokens of the `pattern` and of the `replacement`.
+    pub fn new(pattern: Vec<Arithm<T, RuleVar<V>, E>>,
+               replacement: Vec<Arithm<T, RuleVar<V>, E>>) -> Self
+    {
+        RewriteRule { pattern, replacement }
+    }
+
+    /// Creates a rule from the tokens of two expressions,
+    /// every variable of the `pattern` is a wildcard.
+    ///
+    /// The variables of the `replacement` are replaced by the wildcard of the same
+    /// variable in the `pattern`, the others are kept as is.
+    pub fn from_expressions(pattern: Expression<T, V, E>, replacement: Expression<T, V, E>)
+                            -> Self
+        where V: PartialEq
+    {
+        let mut wildcards: Vec<V> = Vec::new();
+        let pattern = pattern.expr.into_iter().map(|arithm| match arithm {
+            Arithm::Operand(operand) => Arithm::Operand(operand),
+            Arithm::Evaluator(evaluator) => Arithm::Evaluator(evaluator),
+            Arithm::Variable(var) => {
+                let index = match wildcards.iter().position(|v| *v == var) {
+                    Some(index) => index,
+                    None => { wildcards.push(var); wildcards.len() - 1 },
+                };
+                Arithm::Variable(RuleVar::Wildcard(WildcardVar(index)))
+            }
+        }).collect();
+        let replacement = replacement.expr.into_iter().map(|arithm| match arithm {
+            Arithm::Operand(operand) => Arithm::Operand(operand),
+            Arithm::Evaluator(evaluator) => Arithm::Evaluator(evaluator),
+            Arithm::Variable(var) => match wildcards.iter().position(|v| *v == var) {
+                Some(index) => Arithm::Variable(RuleVar::Wildcard(WildcardVar(index))),
+                None => Arithm::Variable(RuleVar::Var(var)),
+            },
+        }).collect();
+        RewriteRule { pattern, replacement }
+    }
+}
+
+/// A pattern variable of a [`RewriteRule`] matching any sub-expression,
+/// wildcards with the same index must match equal sub-expressions.
+///
+/// [`RewriteRule`]: ../expression/struct.RewriteRule.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WildcardVar(pub usize);
+
+/// The variables of the tokens of a [`RewriteRule`].
+///
+/// [`RewriteRule`]: ../expression/struct.RewriteRule.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RuleVar<V> {
+    /// Matches any sub-expression.
+    Wildcard(WildcardVar),
+    /// Matches only this variable.
+    Var(V),
+}
+
+/// Construct an [`Expression`] token by token, without any parsing.
+///
+/// ```
+/// # #[cfg(feature = "int-evaluator")] {
+/// use ripin::expression::ExpressionBuilder;
+/// use ripin::evaluate::{IntEvaluator, IntExpr};
+///
+/// let expr: IntExpr<i32> = ExpressionBuilder::new()
+///     .push_operand(3)
+///     .push_operand(4)
+///     .push_evaluator(IntEvaluator::Add)
+///     .build()
+///     .unwrap();
+/// assert_eq!(expr.evaluate(), Ok(7));
+/// # }
+/// ```
+///
+/// [`Expression`]: ../expression/struct.Expression.html
+#[derive(Debug, Clone)]
+pub struct ExpressionBuilder<T, V, E: Evaluate<T>> {
+    expr: Vec<Arithm<T, V, E>>,
+}
+
+impl<T, V, E: Evaluate<T>> Default for ExpressionBuilder<T, V, E> {
+    fn default() -> Self {
+        ExpressionBuilder::new()
+    }
+}
+
+impl<T, V, E: Evaluate<T>> ExpressionBuilder<T, V, E> {
+    /// Creates an empty `ExpressionBuilder`.
+    pub fn new() -> ExpressionBuilder<T, V, E> {
+        ExpressionBuilder { expr: Vec::new() }
+    }
+
+    /// Appends an `Operand` to the expression.
+    pub fn push_operand(mut self, operand: T) -> Self {
+        self.expr.push(Arithm::Operand(operand));
+        self
+    }
+
+    /// Appends a `Variable` to the expression.
+    pub fn push_variable(mut self, variable: V) -> Self {
+        self.expr.push(Arithm::Variable(variable));
+        self
+    }
+
+    /// Appends an `Evaluator` to the expression.
+    pub fn push_evaluator(mut self, evaluator: E) -> Self {
+        self.expr.push(Arithm::Evaluator(evaluator));
+        self
+    }
+
+    /// Checks the validity of the accumulated tokens and
+    /// returns the corresponding `Expression`.
+    pub fn build(self) -> Result<Expression<T, V, E>, OperandErr> {
+        Expression::check_validity(&self.expr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&self.expr),
+            expr: self.expr,
+        })
+    }
+}
+
+/// Parse an [`Expression`] one token at a time,
+/// useful when tokens come from a stream.
+///
+/// Tokens consuming more operands than available are rejected
+/// as soon as they are pushed, the parser stays usable.
+///
+/// ```
+/// # #[cfg(feature = "int-evaluator")] {
+/// use ripin::expression::ExpressionParser;
+/// use ripin::evaluate::IntExpr;
+///
+/// let mut parser = ExpressionParser::new();
+/// for token in "3 4 +".split_whitespace() {
+///     parser.push_token(token).unwrap();
+/// }
+/// let expr: IntExpr<i32> = parser.finalize().unwrap();
+/// assert_eq!(expr.evaluate(), Ok(7));
+/// # }
+/// ```
+///
+/// [`Expression`]: ../expression/struct.Expression.html
+#[derive(Debug, Clone)]
+pub struct ExpressionParser<T, V, E: Evaluate<T>> {
+    expr: Vec<Arithm<T, V, E>>,
+    depth: usize,
+}
+
+impl<T, V, E: Evaluate<T>> Default for ExpressionParser<T, V, E> {
+    fn default() -> Self {
+        ExpressionParser { expr: Vec::new(), depth: 0 }
+    }
+}
+
+impl<T, V, E: Evaluate<T>> ExpressionParser<T, V, E> {
+    /// Creates a parser without any token.
+    pub fn new() -> Self {
+        ExpressionParser::default()
+    }
+
+    /// Parses and appends a token, the error position
+    /// is the number of tokens already pushed.
+    #[allow(clippy::type_complexity)]
+    pub fn push_token<A>(&mut self, token: A)
+                         -> Result<(), ExprResult<<E as TryFromRef<A>>::Err,
+                                                  <V as TryFromRef<A>>::Err,
+                                                  <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>
+    {
+        let arithm = Expression::<T, V, E>::parse_token(self.expr.len(), &token)?;
+        self.depth = match arithm {
+            Arithm::Operand(_) | Arithm::Variable(_) => self.depth + 1,
+            Arithm::Evaluator(ref evaluator) => {
+                let needed = evaluator.operands_needed();
+                let available = self.depth;
+                let err = OperandErr::NotEnoughOperand { needed, available };
+                let depth = available.checked_sub(needed).ok_or(ExprResult::OperandErr(err))?;
+                depth + evaluator.operands_generated()
+            }
+        };
+        self.expr.push(arithm);
+        Ok(())
+    }
+
+    /// Returns the number of values the pushed tokens leave on the stack.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Constructs the `Expression` from the pushed tokens.
+    pub fn finalize(self) -> Result<Expression<T, V, E>, OperandErr> {
+        Expression::check_validity(&self.expr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&self.expr),
+            expr: self.expr,
+        })
+    }
+}
+
+/// Stream of string tokens not yet parsed, keeping track of the position
+/// of the next token, to mix expressions with tokens of other formats.
+///
+/// ```
+/// use ripin::expression::TokenStream;
+///
+/// let mut tokens = TokenStream::split_whitespace("3 4 +");
+/// assert_eq!(tokens.peek(), Some("3"));
+/// assert_eq!(tokens.next(), Some("3"));
+/// assert_eq!(tokens.position(), 1);
+/// ```
+///
+/// Tokens can be of any type, [`Expression::from_iter()`] parses
+/// its tokens through a stream.
+///
+/// [`Expression::from_iter()`]: ../expression/struct.Expression.html#method.from_iter
+pub struct TokenStream<I: Iterator> {
+    tokens: Peekable<I>,
+    position: usize,
+}
+
+impl<I: Iterator> TokenStream<I> {
+    /// Creates a stream of the given tokens.
+    pub fn new<J>(tokens: J) -> Self
+        where J: IntoIterator<IntoIter=I, Item=I::Item>
+    {
+        TokenStream { tokens: tokens.into_iter().peekable(), position: 0 }
+    }
+
+    /// Returns the next token without consuming it.
+    pub fn peek(&mut self) -> Option<I::Item>
+        where I::Item: Clone
+    {
+        self.tokens.peek().cloned()
+    }
+
+    /// Returns the position of the next token, the number of tokens already consumed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> TokenStream<SplitWhitespace<'a>> {
+    /// Creates a stream of the whitespace separated tokens of a [`str`].
+    ///
+    /// [`str`]: https://doc.rust-lang.org/std/str/index.html
+    pub fn split_whitespace(s: &'a str) -> Self {
+        TokenStream::new(s.split_whitespace())
+    }
+}
+
+impl<I: Iterator> Iterator for TokenStream<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let token = self.tokens.next()?;
+        self.position += 1;
+        Some(token)
+    }
+}
+
+impl<I: Iterator> fmt::Debug for TokenStream<I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TokenStream").field("position", &self.position).finish()
+    }
+}
+
+/// An [`Expression`] allowed to leave more than one value on the stack,
+/// useful to return multiple outputs from a single expression.
+///
+/// [`Expression`]: ../expression/struct.Expression.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MultiExpression<T, V, E: Evaluate<T>>(Expression<T, V, E>);
+
+impl<T: Copy, V: Clone, E: Evaluate<T> + Copy> MultiExpression<T, V, E> {
+    /// Evaluate `RPN` expressions. Returns all the values remaining on the stack,
+    /// from the bottom to the top, or the
+    /// [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
+    pub fn evaluate_all(&self) -> Result<Vec<T>, E::Err>
+        where (): From<V>
+    {
+        self.evaluate_all_with_variables(&DummyVariables::default())
+            .map_err(|err| {
+                match err {
+                    EvalErr::EvalError(err) => err,
+                    _ => unreachable!(),
+                }
+            })
+    }
+
+    /// Evaluate `RPN` expressions containing variables. Returns all the values
+    /// remaining on the stack, from the bottom to the top, or the
+    /// [`evaluate Error`](../evaluate/trait.Evaluate.html#associatedtype.Err).
+    pub fn evaluate_all_with_variables<I, M, C>(&self, variables: &C)
+                                             -> Result<Vec<T>, EvalErr<V, E::Err>>
+        where V: Into<I>,
+              C: GetVariableOwned<I, M, Output=T> + ?Sized
+    {
+        let mut stack = self.0.evaluate_to_stack(variables)?;
+        let mut values = Vec::with_capacity(stack.len());
+        while let Some(value) = stack.pop() {
+            values.push(value);
+        }
+        values.reverse();
+        Ok(values)
+    }
+}
+
+impl<T, V, E: Evaluate<T>> MultiExpression<T, V, E> {
+    /// Same as [`Expression::from_iter()`] but accepts expressions
+    /// leaving any non-zero number of values on the stack.
+    ///
+    /// [`Expression::from_iter()`]: ../expression/struct.Expression.html#method.from_iter
+    #[allow(clippy::type_complexity)]
+    pub fn from_iter<A, I>(iter: I)
+                           -> Result<MultiExpression<T, V, E>,
+                                     ExprResult<<E as TryFromRef<A>>::Err,
+                                                <V as TryFromRef<A>>::Err,
+                                                <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>,
+              I: IntoIterator<Item=A>
+    {
+        MultiExpression::from_token_stream(&mut TokenStream::new(iter))
+    }
+
+    /// Same as [`Expression::from_token_stream()`] but accepts expressions
+    /// leaving any non-zero number of values on the stack.
+    ///
+    /// [`Expression::from_token_stream()`]: ../expression/struct.Expression.html#method.from_token_stream
+    #[allow(clippy::type_complexity)]
+    pub fn from_token_stream<A, I>(tokens: &mut TokenStream<I>)
+                                   -> Result<MultiExpression<T, V, E>,
+                                             ExprResult<<E as TryFromRef<A>>::Err,
+                                                        <V as TryFromRef<A>>::Err,
+                                                        <T as TryFromRef<A>>::Err>>
+        where T: TryFromRef<A>,
+              V: TryFromRef<A>,
+              E: TryFromRef<A>,
+              I: Iterator<Item=A>
+    {
+        let offset = TokenStream::position(tokens);
+        Expression::parse_tokens(offset, tokens.by_ref()).and_then(|final_expr| {
+            match Expression::count_results(&final_expr) {
+                Ok(0) => {
+                    let err = OperandErr::NotEnoughOperand { needed: 1, available: 0 };
+                    Err(ExprResult::OperandErr(err))
+                }
+                Ok(_) => Ok(MultiExpression(Expression {
+                    max_stack: Expression::compute_stack_max(&final_expr),
+                    expr: final_expr,
+                })),
+                Err(err) => Err(ExprResult::OperandErr(err)),
+            }
+        })
+    }
+}
+
+/// The concatenation of two [`Expressions`], leaving both
+/// of their results on the stack (cf. `(3 4 +) (2 neg)`).
+///
+/// It is returned by [`Expression::cat()`] or by adding two expressions.
+///
+/// [`Expressions`]: ../expression/struct.Expression.html
+/// [`Expression::cat()`]: ../expression/struct.Expression.html#method.cat
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CatExpression<T, V, E: Evaluate<T>> {
+    expr: Vec<Arithm<T, V, E>>,
+}
+
+impl<T, V, E: Evaluate<T>> CatExpression<T, V, E> {
+    /// Applies `evaluator` on both results, returns an error
+    /// if the evaluator doesn't reduce them to a single value.
+    pub fn apply(self, evaluator: E) -> Result<Expression<T, V, E>, OperandErr> {
+        let mut expr = self.expr;
+        expr.push(Arithm::Evaluator(evaluator));
+        Expression::check_validity(&expr)?;
+        Ok(Expression {
+            max_stack: Expression::compute_stack_max(&expr),
+            expr,
+        })
+    }
+
+    /// Converts the concatenation into a [`MultiExpression`] returning both results.
+    ///
+    /// [`MultiExpression`]: ../expression/struct.MultiExpression.html
+    pub fn into_multi(self) -> MultiExpression<T, V, E> {
+        MultiExpression(Expression {
+            max_stack: Expression::compute_stack_max(&self.expr),
+            expr: self.expr,
+        })
+    }
+}
+
+impl<T, V, E: Evaluate<T>> Add for Expression<T, V, E> {
+    type Output = CatExpression<T, V, E>;
+
+    fn add(self, other: Expression<T, V, E>) -> Self::Output {
+        self.cat(other)
+    }
+}
+
+impl<T, V, E: Evaluate<T>> Expression<T, V, E> {
+    fn compute_stack_max(expr: &[Arithm<T, V, E>]) -> usize {
+        expr.iter() .map(|arithm| {
+            match *arithm {
+                Arithm::Operand(_) |
+                Arithm::Variable(_) => 1,
+                Arithm::Evaluator(ref op) => {
+                    op.operands_generated() as isize - op.operands_needed() as isize
+                }
+            }
+        })
+        .fold((0, 0isize), |(max, acc_count), count| {
+            let acc = (acc_count + count) as usize;
+            if acc > max {
+                (acc, acc as isize)
+            } else {
+                (max, acc as isize)
+            }
+        }).0
+    }
+}
+
+impl<T, V, E> Expression<T, V, E>
+    where T: fmt::Display,
+          V: fmt::Display,
+          E: fmt::Display + Evaluate<T>
+{
+    /// Converts the expression to a fully parenthesized infix notation
+    /// (cf. `3 4 + 2 *` gives `((3 + 4) * 2)`).
+    ///
+    /// Binary evaluators are written between their operands, others are written
+    /// like functions (cf. `sqrt(9)`). Returns `None` if the expression
+    /// contains an evaluator that doesn't generate exactly one operand.
+    pub fn to_infix(&self) -> Option<String> {
+        let mut stack = Stack::with_capacity(self.max_stack);
+        for arithm in &self.expr {
+            match *arithm {
+                Arithm::Operand(ref operand) => stack.push(operand.to_string()),
+                Arithm::Variable(ref variable) => stack.push(variable.to_string()),
+                Arithm::Evaluator(ref evaluator) => {
+                    if evaluator.operands_generated() != 1 {
+                        return None
+                    }
+                    let mut args = Vec::with_capacity(evaluator.operands_needed());
+                    for _ in 0..evaluator.operands_needed() {
+                        args.push(stack.pop()?);
+                    }
+                    args.reverse();
+                    let infix = match args.len() {
+                        0 => evaluator.to_string(),
+                        2 => format!("({} {} {})", args[0], evaluator, args[1]),
+                        _ => format!("{}({})", evaluator, args.join(", ")),
+                    };
+                    stack.push(infix)
+                }
+            }
+        }
+        stack.pop()
+    }
+
+    /// Converts the expression to the Polish prefix notation, where each evaluator
+    /// is written before its operands (cf. `3 4 + 2 *` gives `* + 3 4 2`).
+    ///
+    /// Returns `None` if the expression contains an evaluator
+    /// that doesn't generate exactly one operand.
+    pub fn to_polish_prefix(&self) -> Option<String> {
+        let mut stack = Stack::with_capacity(self.max_stack);
+        for arithm in &self.expr {
+            match *arithm {
+                Arithm::Operand(ref operand) => stack.push(operand.to_string()),
+                Arithm::Variable(ref variable) => stack.push(variable.to_string()),
+                Arithm::Evaluator(ref evaluator) => {
+                    if evaluator.operands_generated() != 1 {
+                        return None
+                    }
+                    let mut tokens = Vec::with_capacity(evaluator.operands_needed() + 1);
+                    for _ in 0..evaluator.operands_needed() {
+                        tokens.push(stack.pop()?);
+                    }
+                    tokens.push(evaluator.to_string());
+                    tokens.reverse();
+                    stack.push(tokens.join(" "))
+                }
+            }
+        }
+        stack.pop()
+    }
+
+    /// Returns the string representation of the expression with tokens
+    /// joined by `sep` instead of the space `Display` uses.
+    ///
+    /// ```
+    /// # #[cfg(feature = "int-evaluator")] {
+    /// use ripin::evaluate::IntExpr;
+    ///
+    /// let expr = IntExpr::<i32>::from_iter("3 4 +".split_whitespace()).unwrap();
+    /// assert_eq!(expr.to_string_with_sep(","), "3,4,+");
+    /// # }
+    /// ```
+    pub fn to_string_with_sep(&self, sep: &str) -> String {
+        Separated(self, sep).to_string()
+    }
+
+    fn fmt_with_sep(&self, f: &mut fmt::Formatter, sep: &str) -> fmt::Result {
+        let len = self.expr.len();
+        for (i, arithm) in self.expr.iter().enumerate() {
+            fmt::Display::fmt(arithm, f)?;
+            if i != len - 1 {
+                f.write_str(sep)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the string representation of each token,
+    /// the same ones `Display` joins with spaces.
+    pub fn to_tokens(&self) -> Vec<String> {
+        self.expr.iter().map(|arithm| arithm.to_string()).collect()
+    }
+
+    /// Counts the occurrences of each evaluator of the expression,
+    /// grouped by their string representation.
+    pub fn count_operations(&self) -> BTreeMap<String, usize> {
+        self.count_operations_by(|evaluator| evaluator.to_string())
+    }
+
+    /// Converts the expression to a [`Graphviz DOT`] graph, each token is a node labeled
+    /// with its string representation and each evaluator node has an edge
+    /// coming from the nodes of the operands it consumes.
+    ///
+    /// [`Graphviz DOT`]: https://graphviz.org/doc/info/lang.html
+    pub fn to_dot_graph(&self) -> String {
+        let mut dot = String::from("digraph expression {\n");
+        let mut stack = Stack::with_capacity(self.max_stack);
+        for (id, arithm) in self.expr.iter().enumerate() {
+            let label = arithm.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+            dot.push_str(&format!("    n{} [label=\"{}\"];\n", id, label));
+            match *arithm {
+                Arithm::Operand(_) | Arithm::Variable(_) => stack.push(id),
+                Arithm::Evaluator(ref evaluator) => {
+                    let start = stack.len() - evaluator.operands_needed();
+                    for &operand in &stack[start..] {
+                        dot.push_str(&format!("    n{} -> n{};\n", operand, id));
+                    }
+                    for _ in 0..evaluator.operands_needed() {
+                        stack.pop();
+                    }
+                    for _ in 0..evaluator.operands_generated() {
+                        stack.push(id);
+                    }
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Converts the expression to a JSON array of its string tokens
+    /// (cf. `["3","4","+"]`), without requiring `serde`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, arithm) in self.expr.iter().enumerate() {
+            if i != 0 {
+                json.push(',');
+            }
+            json.push('"');
+            for c in arithm.to_string().chars() {
+                match c {
+                    '"' => json.push_str("\\\""),
+                    '\\' => json.push_str("\\\\"),
+                    '\n' => json.push_str("\\n"),
+                    '\r' => json.push_str("\\r"),
+                    '\t' => json.push_str("\\t"),
+                    c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => json.push(c),
+                }
+            }
+            json.push('"');
+        }
+        json.push(']');
+        json
+    }
+}
+
+impl<T, V, E> fmt::Display for Arithm<T, V, E>
+    where T: fmt::Display,
+          V: fmt::Display,
+          E: fmt::Display + Evaluate<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Arithm::Operand(ref operand) => operand.fmt(f),
+            Arithm::Variable(ref variable) => variable.fmt(f),
+            Arithm::Evaluator(ref evaluator) => evaluator.fmt(f),
+        }
+    }
+}
+
+impl<T, V, E> fmt::Display for Expression<T, V, E>
+    where T: fmt::Display,
+          V: fmt::Display,
+          E: fmt::Display + Evaluate<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_sep(f, " ")
+    }
+}
+
+struct Separated<'a, T: 'a, V: 'a, E: 'a + Evaluate<T>>(&'a Expression<T, V, E>, &'a str);
+
+impl<'a, T, V, E> fmt::Display for Separated<'a, T, V, E>
+    where T: fmt::Display,
+          V: fmt::Display,
+          E: fmt::Display + Evaluate<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_with_sep(f, self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expression::Expression;
+    #[cfg(any(feature = "float-evaluator", feature = "int-evaluator"))]
+    use expression::{Arithm, OperandErr};
+    #[cfg(feature = "int-evaluator")]
+    use expression::{EvalErr, ExprResult, ExpressionBuilder, ExpressionParser, MultiExpression};
+    #[cfg(feature = "float-evaluator")]
+    use expression::VariableIndexError;
+    #[cfg(feature = "float-evaluator")]
+    use evaluate::{FloatEvaluator, FloatExpr, VariableFloatExpr};
+    #[cfg(feature = "int-evaluator")]
+    use evaluate::{IntEvaluator, IntExpr, VariableIntExpr};
+    use variable::DummyVariable;
+    #[cfg(any(feature = "float-evaluator", feature = "int-evaluator"))]
+    use variable::IndexVar;
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn cat() {
+        let lhs = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let rhs = FloatExpr::<f32>::from_iter("2 neg".split_whitespace()).unwrap();
+
+        let cat = lhs.clone() + rhs.clone();
+        assert_eq!(cat.clone().into_multi().evaluate_all(), Ok(vec![7.0, -2.0]));
+
+        let expr = cat.clone().apply(FloatEvaluator::Mul).unwrap();
+        assert_eq!(expr.evaluate(), Ok(-14.0));
+        assert_eq!(expr, lhs.combine(rhs, FloatEvaluator::Mul).unwrap());
+
+        let res = cat.apply(FloatEvaluator::Neg);
+        assert_eq!(res, Err(OperandErr::TooManyOperands { found: 2 }));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn map_evaluators() {
+        use core::cell::Cell;
+        use evaluate::Evaluate;
+        use stack::Stack;
+
+        #[derive(Debug, Copy, Clone)]
+        struct LoggingEvaluator<'a, E> {
+            inner: E,
+            calls: &'a Cell<usize>,
+        }
+
+        impl<'a, T, E: Evaluate<T>> Evaluate<T> for LoggingEvaluator<'a, E> {
+            type Err = E::Err;
+
+            fn operands_needed(&self) -> usize {
+                self.inner.operands_needed()
+            }
+
+            fn operands_generated(&self) -> usize {
+                self.inner.operands_generated()
+            }
+
+            fn evaluate(self, stack: &mut Stack<T>) -> Result<(), Self::Err> {
+                self.calls.set(self.calls.get() + 1);
+                self.inner.evaluate(stack)
+            }
+        }
+
+        let calls = Cell::new(0);
+        let expr = FloatExpr::<f32>::from_iter("3 4 + 2 * neg".split_whitespace()).unwrap();
+        let expected = expr.evaluate();
+        let logged = expr.clone().map_evaluators(|inner| LoggingEvaluator { inner, calls: &calls });
+        assert_eq!(logged.unwrap().evaluate(), expected);
+        assert_eq!(calls.get(), 3);
+
+        let res = expr.map_evaluators(|_| FloatEvaluator::Neg);
+        assert_eq!(res, Err(OperandErr::TooManyOperands { found: 3 }));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn referenced_variables() {
+        use convert_ref::TryFromRef;
+
+        let tokens = "$0 $1 $0 + *".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        let var = |token| IndexVar::try_from_ref(&token).unwrap();
+        assert_eq!(expr.referenced_variables(), vec![&var("$0"), &var("$1")]);
+        assert_eq!(expr.referenced_variable_count(), 2);
+        assert_eq!(expr.variable_count(), 3);
+    }
+
+    #[cfg(all(feature = "float-evaluator", feature = "int-evaluator"))]
+    #[test]
+    fn to_dot_graph() {
+        let expr = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let expected = "digraph expression {\n    \
+                        n0 [label=\"3\"];\n    \
+                        n1 [label=\"4\"];\n    \
+                        n2 [label=\"+\"];\n    \
+                        n0 -> n2;\n    \
+                        n1 -> n2;\n\
+                        }\n";
+        assert_eq!(expr.to_dot_graph(), expected);
+
+        let expr = IntExpr::<i32>::from_iter("3 4 swap -".split_whitespace()).unwrap();
+        let dot = expr.to_dot_graph();
+        assert!(dot.contains("n2 [label=\"swap\"];"));
+        assert_eq!(dot.matches("n2 -> n3;").count(), 2);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn approximate_equal() {
+        let expr = FloatExpr::<f64>::from_iter("1 3 /".split_whitespace()).unwrap();
+        let expected = FloatExpr::<f64>::from_iter("0.333333".split_whitespace()).unwrap();
+        assert!(expr.approximate_equal(&expected, 1e-5));
+        assert!(!expr.approximate_equal(&expected, 1e-7));
+
+        let nan = FloatExpr::<f64>::from_iter("-1 sqrt".split_whitespace()).unwrap();
+        assert!(!nan.approximate_equal(&nan, 1e-5));
+    }
+
+    #[cfg(all(feature = "std", feature = "float-evaluator", feature = "int-evaluator"))]
+    #[test]
+    fn from_reader() {
+        use std::io::Cursor;
+        use expression::{ParseExprError, ReadExprError};
+
+        let expr_str = "3 4 +\n  2 *\n\n$0 -";
+        let expected = VariableFloatExpr::<f32, IndexVar>::from_iter(expr_str.split_whitespace());
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_reader(Cursor::new(expr_str));
+        assert_eq!(expr.unwrap(), expected.unwrap());
+
+        let res = IntExpr::<i32>::from_reader(Cursor::new("3 4\n& +"));
+        match res {
+            Err(ReadExprError::Parse(err)) => {
+                assert_eq!(err, ParseExprError::InvalidToken("&".to_string()))
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        let res = IntExpr::<i32>::from_reader(Cursor::new("3 4"));
+        match res {
+            Err(ReadExprError::Parse(ParseExprError::OperandErr(_))) => (),
+            _ => panic!("expected an operand error"),
+        }
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn json() {
+        use evaluate::VariableFloatExprNamed;
+        use expression::{JsonParseError, ParseExprError};
+
+        let expr = FloatExpr::<f32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        assert_eq!(expr.to_json(), r#"["3","4","+","2","*"]"#);
+        assert_eq!(FloatExpr::<f32>::from_json(&expr.to_json()), Ok(expr));
+
+        let tokens = vec!["$a\"b\\", "$\u{1}\n", "$é", "+", "+"];
+        let expr = VariableFloatExprNamed::<f32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.to_json(), r#"["$a\"b\\","$\u0001\n","$é","+","+"]"#);
+        assert_eq!(VariableFloatExprNamed::<f32>::from_json(&expr.to_json()), Ok(expr));
+
+        let expr = VariableFloatExprNamed::<f32>::from_json(r#" [ "$\ud83d\ude00" ,"$\/", "+" ] "#);
+        assert_eq!(expr.unwrap().to_string(), "$\u{1f600} $/ +");
+
+        let empty = OperandErr::NotEnoughOperand { needed: 1, available: 0 };
+        assert_eq!(FloatExpr::<f32>::from_json("[]"),
+                   Err(JsonParseError::Parse(ParseExprError::OperandErr(empty))));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["3", "&"]"#),
+                   Err(JsonParseError::Parse(ParseExprError::InvalidToken("&".to_string()))));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["3" "4"]"#), Err(JsonParseError::Malformed(5)));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["3", 4]"#), Err(JsonParseError::Malformed(6)));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["3",]"#), Err(JsonParseError::Malformed(5)));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["3"] x"#), Err(JsonParseError::Malformed(6)));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["\x"]"#), Err(JsonParseError::Malformed(3)));
+        assert_eq!(FloatExpr::<f32>::from_json(r#"["3"#), Err(JsonParseError::Malformed(3)));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn fold_tokens() {
+        let expr = IntExpr::<i32>::from_iter("3 4 5 + *".split_whitespace()).unwrap();
+
+        let operands = expr.fold_tokens(0, |count, arithm| match *arithm {
+            Arithm::Operand(_) => count + 1,
+            _ => count,
+        });
+        assert_eq!(operands, 3);
+
+        let evaluators = expr.fold_tokens(Vec::new(), |mut evaluators, arithm| {
+            if let Arithm::Evaluator(evaluator) = *arithm {
+                evaluators.push(evaluator)
+            }
+            evaluators
+        });
+        assert_eq!(evaluators, vec![IntEvaluator::Add, IntEvaluator::Mul]);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn evaluate_with_stack_limit() {
+        use variable::DummyVariables;
+
+        let expr = IntExpr::<i32>::from_iter("1 2 3 4 5 + + + +".split_whitespace()).unwrap();
+        let variables = DummyVariables::default();
+        assert_eq!(expr.evaluate_with_stack_limit(&variables, 3),
+                   Err(EvalErr::StackOverflow { limit: 3 }));
+        assert_eq!(expr.evaluate_with_stack_limit(&variables, 5), Ok(15));
+    }
+
+    #[cfg(all(feature = "float-evaluator", feature = "int-evaluator"))]
+    #[test]
+    fn contains() {
+        use convert_ref::TryFromRef;
+
+        let expr = FloatExpr::<f32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        assert!(expr.contains_evaluator(&FloatEvaluator::Add));
+        assert!(!expr.contains_evaluator(&FloatEvaluator::Div));
+        assert!(expr.contains_operand(&3.0_f32));
+        assert!(!expr.contains_operand(&5.0_f32));
+
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter("$0 $2 +".split_whitespace()).unwrap();
+        assert!(expr.contains_variable(&IndexVar::try_from_ref(&"$2").unwrap()));
+        assert!(!expr.contains_variable(&IndexVar::try_from_ref(&"$1").unwrap()));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    #[allow(clippy::clone_on_copy)]
+    fn arithm_clone_eq_hash() {
+        use std::collections::HashSet;
+
+        let add: Arithm<i32, usize, IntEvaluator<i32>> = Arithm::Evaluator(IntEvaluator::Add);
+        let cloned = add.clone();
+        assert_eq!(add, cloned);
+        assert_ne!(add, Arithm::Evaluator(IntEvaluator::Sub));
+
+        let tokens: HashSet<_> = vec![add, Arithm::Variable(2), Arithm::Evaluator(IntEvaluator::Neg)]
+            .into_iter().collect();
+        assert!(tokens.contains(&cloned));
+        assert!(tokens.contains(&Arithm::Variable(2)));
+        assert!(!tokens.contains(&Arithm::Variable(3)));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn arithm_accessors() {
+        let operand: Arithm<f32, usize, FloatEvaluator<f32>> = Arithm::Operand(3.0);
+        assert!(operand.is_operand() && !operand.is_variable() && !operand.is_evaluator());
+        assert_eq!(operand.as_operand(), Some(&3.0));
+        assert_eq!(operand.as_variable(), None);
+        assert_eq!(operand.as_evaluator(), None);
+
+        let variable: Arithm<f32, usize, FloatEvaluator<f32>> = Arithm::Variable(1);
+        assert!(!variable.is_operand() && variable.is_variable() && !variable.is_evaluator());
+        assert_eq!(variable.as_operand(), None);
+        assert_eq!(variable.as_variable(), Some(&1));
+        assert_eq!(variable.as_evaluator(), None);
+
+        let evaluator: Arithm<f32, usize, _> = Arithm::Evaluator(FloatEvaluator::Add);
+        assert!(!evaluator.is_operand() && !evaluator.is_variable() && evaluator.is_evaluator());
+        assert_eq!(evaluator.as_operand(), None);
+        assert_eq!(evaluator.as_variable(), None);
+        assert_eq!(evaluator.as_evaluator(), Some(&FloatEvaluator::Add));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn repeat() {
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter("$0 2 *".split_whitespace()).unwrap();
+        let repeated = expr.repeat(3);
+        let expected = VariableFloatExpr::<f32, IndexVar>::from_iter("$0 2 * 2 * 2 *".split_whitespace());
+        assert_eq!(repeated, expected.unwrap());
+        assert_eq!(repeated.evaluate_with_variables(&[1.0]), Ok(8.0));
+
+        let expr = FloatExpr::<f32>::from_iter("1 3 + 2 /".split_whitespace()).unwrap();
+        assert_eq!(expr.repeat(2).evaluate(), Ok(2.5));
+        assert_eq!(expr.repeat(0).evaluate(), Ok(1.0));
+    }
+
+    #[test]
+    fn evaluate_with_context() {
+        use convert_ref::TryFromRef;
+        use evaluate::{Evaluate, EvaluateStateful};
+        use stack::Stack;
+
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        enum CountingEvaluator {
+            Add,
+            Mul,
+        }
+
+        impl Evaluate<i32> for CountingEvaluator {
+            type Err = ();
+
+            fn operands_needed(&self) -> usize {
+                2
+            }
+
+            fn operands_generated(&self) -> usize {
+                1
+            }
+
+            #[allow(clippy::unit_arg)]
+            fn evaluate(self, stack: &mut Stack<i32>) -> Result<(), Self::Err> {
+                let (a, b) = ::pop_two_operands(stack).unwrap();
+                match self {
+                    CountingEvaluator::Add => Ok(stack.push(a + b)),
+                    CountingEvaluator::Mul => Ok(stack.push(a * b)),
+                }
+            }
+        }
+
+        impl EvaluateStateful<i32, usize> for CountingEvaluator {
+            fn evaluate_stateful(self, stack: &mut Stack<i32>, count: &mut usize) -> Result<(), ()> {
+                *count += 1;
+                self.evaluate(stack)
+            }
+        }
+
+        impl<'a> TryFromRef<&'a str> for CountingEvaluator {
+            type Err = ();
+            fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+                match *expr {
+                    "+" => Ok(CountingEvaluator::Add),
+                    "*" => Ok(CountingEvaluator::Mul),
+                    _ => Err(()),
+                }
+            }
+        }
+
+        type CountingExpr = Expression<i32, DummyVariable, CountingEvaluator>;
+
+        let expr = CountingExpr::from_iter("3 4 + 2 * 5 +".split_whitespace()).unwrap();
+        let mut count = 0;
+        assert_eq!(expr.evaluate_with_context(&mut count), Ok(19));
+        assert_eq!(count, 3);
+
+        assert_eq!(expr.evaluate_with_context(&mut count), Ok(19));
+        assert_eq!(count, 6);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn evaluate_with_variables_mut() {
+        use std::collections::HashMap;
+        use convert_ref::TryFromRef;
+        use evaluate::{Evaluate, EvaluateStateful};
+        use expression::EvalErr;
+        use stack::Stack;
+        use variable::{GetVariableMut, IndexVar};
+
+        /// Adds the top of the stack to the `$0` accumulator and pushes its new value.
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        struct AddAssign;
+
+        impl Evaluate<i32> for AddAssign {
+            type Err = ();
+
+            fn operands_needed(&self) -> usize {
+                1
+            }
+
+            fn operands_generated(&self) -> usize {
+                1
+            }
+
+            fn evaluate(self, _stack: &mut Stack<i32>) -> Result<(), Self::Err> {
+                Err(())
+            }
+        }
+
+        impl EvaluateStateful<i32, HashMap<usize, i32>> for AddAssign {
+            fn evaluate_stateful(self, stack: &mut Stack<i32>, accs: &mut HashMap<usize, i32>)
+                                 -> Result<(), ()>
+            {
+                let accumulator = accs.get_variable_mut(0).ok_or(())?;
+                *accumulator += stack.pop().ok_or(())?;
+                stack.push(*accumulator);
+                Ok(())
+            }
+        }
+
+        impl<'a> TryFromRef<&'a str> for AddAssign {
+            type Err = ();
+            fn try_from_ref(expr: &&'a str) -> Result<Self, Self::Err> {
+                match *expr {
+                    "+=" => Ok(AddAssign),
+                    _ => Err(()),
+                }
+            }
+        }
+
+        let mut accumulators = HashMap::new();
+        accumulators.insert(0, 0);
+
+        let expr = Expression::<i32, IndexVar, AddAssign>::from_iter("2 +=".split_whitespace());
+        let expr = expr.unwrap();
+        for _ in 0..3 {
+            expr.evaluate_with_variables_mut(&mut accumulators).unwrap();
+        }
+        assert_eq!(accumulators[&0], 6);
+
+        let expr = Expression::<i32, IndexVar, AddAssign>::from_iter("$0 +=".split_whitespace());
+        assert_eq!(expr.unwrap().evaluate_with_variables_mut(&mut accumulators), Ok(12));
+        assert_eq!(accumulators[&0], 12);
+
+        let expr = Expression::<i32, IndexVar, AddAssign>::from_iter("$1 +=".split_whitespace());
+        match expr.unwrap().evaluate_with_variables_mut(&mut accumulators) {
+            Err(EvalErr::VariableNotFound(var)) => assert_eq!(usize::from(var), 1),
+            res => panic!("{:?}", res),
+        }
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn comments() {
+        use expression::DEFAULT_COMMENT_CHAR;
+
+        let expected = FloatExpr::<f32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+
+        let expr_str = "3 4 + # adds three and four\n2 *";
+        let expr = FloatExpr::<f32>::from_str_with_comments(expr_str, DEFAULT_COMMENT_CHAR).unwrap();
+        assert_eq!(expr, expected);
+
+        let expr_str = "; only a comment\n3 4 +; adds\n\n2 * ;multiplies";
+        let expr = FloatExpr::<f32>::from_str_with_comments(expr_str, ';').unwrap();
+        assert_eq!(expr, expected);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn custom_delimiter() {
+        let expected = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+
+        let expr = FloatExpr::<f32>::from_str_with_delimiter("3,4,+", ',').unwrap();
+        assert_eq!(expr, expected);
+
+        let expr = FloatExpr::<f32>::from_str_with_delimiter(",3,,4,+,", ',').unwrap();
+        assert_eq!(expr, expected);
+
+        let expr = FloatExpr::<f32>::from_str_with_pattern("3\t4 +", char::is_whitespace).unwrap();
+        assert_eq!(expr, expected);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn len_and_token_at() {
+        let expr_str = "3 4 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = FloatExpr::<f32>::from_iter(tokens).unwrap();
+        assert_eq!(expr.len(), 3);
+        assert!(!expr.is_empty());
+        match expr.token_at(2) {
+            Some(&Arithm::Evaluator(FloatEvaluator::Add)) => (),
+            other => panic!("{:?}", other),
+        }
+        assert!(expr.token_at(10).is_none());
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn iter_tokens() {
+        let expr_str = "3 $0 +";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let mut iter = expr.iter();
+        match (iter.next(), iter.next(), iter.next(), iter.next()) {
+            (Some(&Arithm::Operand(3.0)),
+             Some(&Arithm::Variable(var)),
+             Some(&Arithm::Evaluator(FloatEvaluator::Add)),
+             None) => assert_eq!(usize::from(var), 0),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!((&expr).into_iter().count(), 3);
+        assert_eq!(expr.into_iter().count(), 3);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn clone_expression() {
+        let expr_str = "3 4 + 2 *";
+        let tokens = expr_str.split_whitespace();
+        let expr = FloatExpr::<f32>::from_iter(tokens).unwrap();
+        let cloned = expr.clone();
+        assert_eq!(expr.into_iter().count(), 5);
+        assert_eq!(cloned.evaluate(), Ok(14.0));
+        assert_eq!(&cloned.to_string(), expr_str);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn equality_and_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of<H: Hash>(value: &H) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let a = IntExpr::<i32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let b = IntExpr::<i32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let c = IntExpr::<i32>::from_iter("4 3 +".split_whitespace()).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert!(a != c);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn variables_operands_evaluators() {
+        let expr_str = "3 $0 $1 + *";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let variables: Vec<usize> = expr.variables().map(|&var| var.into()).collect();
+        assert_eq!(variables, vec![0, 1]);
+        let operands: Vec<_> = expr.operands().cloned().collect();
+        assert_eq!(operands, vec![3.0]);
+        let evaluators: Vec<_> = expr.evaluators().cloned().collect();
+        assert_eq!(evaluators, vec![FloatEvaluator::Add, FloatEvaluator::Mul]);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn multi_expression_evaluate_all() {
+        use expression::TokenStream;
+
+        type MultiIntExpr = MultiExpression<i32, DummyVariable, IntEvaluator<i32>>;
+
+        let expr_str = "3 4 + 2 5 *";
+        let tokens = expr_str.split_whitespace();
+        let expr = MultiIntExpr::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_all(), Ok(vec![7, 10]));
+
+        let tokens = "+".split_whitespace();
+        match MultiIntExpr::from_iter(tokens) {
+            Err(ExprResult::OperandErr(OperandErr::NotEnoughOperand { needed: 2, available: 0 })) => (),
+            other => panic!("{:?}", other),
+        }
+
+        let mut tokens = TokenStream::split_whitespace("rpn 3 &");
+        tokens.next();
+        match MultiIntExpr::from_token_stream(&mut tokens) {
+            Err(ExprResult::InvalidToken { position: 2, .. }) => (),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn expression_builder() {
+        let expr: IntExpr<i32> = ExpressionBuilder::new()
+            .push_operand(3)
+            .push_operand(4)
+            .push_evaluator(IntEvaluator::Add)
+            .build()
+            .unwrap();
+        assert_eq!(expr.evaluate(), Ok(7));
+        assert_eq!(&expr.to_string(), "3 4 +");
+
+        let res: Result<IntExpr<i32>, _> = ExpressionBuilder::new()
+            .push_operand(3)
+            .push_operand(4)
+            .build();
+        assert_eq!(res, Err(OperandErr::TooManyOperands { found: 2 }));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn combine_expressions() {
+        let a = IntExpr::<i32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let b = IntExpr::<i32>::from_iter("2 3 *".split_whitespace()).unwrap();
+        let expected = IntExpr::<i32>::from_iter("3 4 + 2 3 * *".split_whitespace()).unwrap();
+        let expr = a.combine(b, IntEvaluator::Mul).unwrap();
+        assert_eq!(expr, expected);
+        assert_eq!(expr.evaluate(), Ok(42));
+
+        let a = IntExpr::<i32>::from_iter("3".split_whitespace()).unwrap();
+        let b = IntExpr::<i32>::from_iter("2".split_whitespace()).unwrap();
+        let res = a.combine(b, IntEvaluator::Neg);
+        assert_eq!(res, Err(OperandErr::TooManyOperands { found: 2 }));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn infix_notation() {
+        let expr = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        assert_eq!(expr.to_infix(), Some("(3 + 4)".to_string()));
+
+        let expr = FloatExpr::<f32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        assert_eq!(expr.to_infix(), Some("((3 + 4) * 2)".to_string()));
+
+        let expr = FloatExpr::<f32>::from_iter("9 sqrt".split_whitespace()).unwrap();
+        assert_eq!(expr.to_infix(), Some("sqrt(9)".to_string()));
+
+        let expr = FloatExpr::<f32>::from_iter("2 4 swap /".split_whitespace()).unwrap();
+        assert_eq!(expr.to_infix(), None);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn map_operands_to_other_type() {
+        use convert_ref::TryFromRef;
+
+        let expr = FloatExpr::<f32>::from_iter("3.5 4 + 2 *".split_whitespace()).unwrap();
+        let converted: FloatExpr<f64> = expr.clone().map_operands(|x| x as f64, |eval| {
+            TryFromRef::try_from_ref(&eval.to_string().as_str()).ok().unwrap()
+        }).unwrap();
+        assert_eq!(converted.evaluate(), Ok(15.0f64));
+        assert_eq!(&converted.to_string(), "3.5 4 + 2 *");
+
+        let res: Result<FloatExpr<f64>, _> = expr.map_operands(|x| x as f64, |_| {
+            FloatEvaluator::Sqrt
+        });
+        assert_eq!(res, Err(OperandErr::TooManyOperands { found: 3 }));
+    }
+
+    #[cfg(all(feature = "std", feature = "float-evaluator"))]
+    #[test]
+    fn partial_evaluation() {
+        use std::collections::HashMap;
+
+        let mut known = HashMap::new();
+        known.insert(0, 4.0);
+
+        let expr_str = "3 $0 + $1 *";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let partial = expr.partial_evaluate(&known);
+        assert_eq!(partial.variables().count(), 1);
+        assert_eq!(partial.len(), 3);
+        assert_eq!(partial.token_at(0), Some(&Arithm::Operand(7.0)));
+
+        known.insert(1, 2.0);
+        assert_eq!(partial.evaluate_with_variables(&known), Ok(14.0));
+        assert_eq!(expr.partial_evaluate(&known).len(), 1);
+    }
+
+    #[cfg(all(feature = "std", feature = "float-evaluator"))]
+    #[test]
+    fn str_variables_evaluation() {
+        use std::collections::HashMap;
+        use expression::EvalErr;
+        use evaluate::VariableFloatExprNamed;
+
+        let mut variables = HashMap::new();
+        variables.insert("x".to_string(), 3.0);
+        variables.insert("y".to_string(), 2.0);
+
+        let expr = VariableFloatExprNamed::<f32>::from_iter("$x $y / $x *".split_whitespace()).unwrap();
+        assert_eq!(expr.evaluate_with_str_variables(&variables), Ok(4.5));
+        assert_eq!(expr.evaluate_with_str_variables(&variables), expr.evaluate_with_variables(&variables));
+
+        let expr = VariableFloatExprNamed::<f32>::from_iter("$x $z +".split_whitespace()).unwrap();
+        match expr.evaluate_with_str_variables(&variables) {
+            Err(EvalErr::VariableNotFound(ref var)) if var.as_ref() == "z" => (),
+            res => panic!("{:?}", res),
+        }
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn constant_folding() {
+        let expr_str = "3 4 + 2 * $0 /";
+        let tokens = expr_str.split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let tokens = "14 $0 /".split_whitespace();
+        let expected = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.constant_fold(), expected);
+
+        let expr = FloatExpr::<f32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        let folded = expr.constant_fold();
+        assert_eq!(folded.to_string(), "14");
+        assert_eq!(folded.evaluate(), Ok(14.0));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn validate_variable_indices() {
+        let tokens = "$0 $2 +".split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let err = VariableIndexError { index: 2, max: Some(1) };
+        assert_eq!(expr.validate_variable_indices(2), Err(err));
+        assert_eq!(expr.validate_variable_indices(3), Ok(()));
+
+        let err = expr.validate_variable_indices(0).unwrap_err();
+        assert_eq!(err, VariableIndexError { index: 0, max: None });
+        assert_eq!(err.to_string(), "variable index 0 out of range (no variable allowed)");
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn incremental_parser() {
+        let mut parser = ExpressionParser::new();
+        for token in &["3", "4", "+"] {
+            parser.push_token(*token).unwrap();
+        }
+        let expr: IntExpr<i32> = parser.finalize().unwrap();
+        let expected = IntExpr::<i32>::from_iter(vec!["3", "4", "+"]).unwrap();
+        assert_eq!(expr, expected);
+        assert_eq!(expr.evaluate(), Ok(7));
+
+        let mut parser = ExpressionParser::<i32, _, IntEvaluator<i32>>::new();
+        parser.push_token("3").unwrap();
+        match parser.push_token("+") {
+            Err(ExprResult::OperandErr(err)) => {
+                assert_eq!(err, OperandErr::NotEnoughOperand { needed: 2, available: 1 })
+            }
+            res => panic!("{:?}", res),
+        }
+        parser.push_token("4").unwrap();
+        assert_eq!(parser.depth(), 2);
+        let res: Result<IntExpr<i32>, _> = parser.finalize();
+        assert_eq!(res, Err(OperandErr::TooManyOperands { found: 2 }));
+    }
+
+    #[cfg(all(feature = "std", feature = "int-evaluator"))]
+    #[test]
+    fn substitute_variable() {
+        use std::collections::HashMap;
+        use convert_ref::TryFromRef;
+        use evaluate::VariableIntExprNamed;
+        use variable::NamedVar;
+
+        let tokens = "2 $x *".split_whitespace();
+        let expr = VariableIntExprNamed::<i32>::from_iter(tokens).unwrap();
+        let tokens = "3 4 +".split_whitespace();
+        let replacement = VariableIntExprNamed::<i32>::from_iter(tokens).unwrap();
+        let target = NamedVar::try_from_ref(&"$x").unwrap();
+
+        let expr = expr.substitute_variable(target, replacement).unwrap();
+        let tokens = "2 3 4 + *".split_whitespace();
+        let expected = VariableIntExprNamed::<i32>::from_iter(tokens).unwrap();
+        assert_eq!(expr, expected);
+
+        let variables: HashMap<String, i32> = HashMap::new();
+        assert_eq!(expr.evaluate_with_variables(&variables), Ok(14));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn symbolic_simplify() {
+        use evaluate::VariableFloatExprNamed;
+
+        let parse = |expr: &str| {
+            VariableFloatExprNamed::<f64>::from_iter(expr.split_whitespace()).unwrap()
+        };
+        assert_eq!(parse("$x 0 +").symbolic_simplify(), parse("$x"));
+        assert_eq!(parse("$x 1 *").symbolic_simplify(), parse("$x"));
+        assert_eq!(parse("$x 0 *").symbolic_simplify(), parse("0"));
+        assert_eq!(parse("0 $x 2 + *").symbolic_simplify(), parse("0"));
+        assert_eq!(parse("$x $y * $x $y * -").symbolic_simplify(), parse("0"));
+        assert_eq!(parse("$x 0 + 1 * $y $y - +").symbolic_simplify(), parse("$x"));
+        assert_eq!(parse("1 $x $y / 1 / *").symbolic_simplify(), parse("$x $y /"));
+
+        let expr = parse("$x 2 swap - 0 +").symbolic_simplify();
+        assert_eq!(expr, parse("$x 2 swap -"));
+        assert_eq!(parse("$x 2 +").symbolic_simplify(), parse("$x 2 +"));
+        assert_eq!(parse("$x 0 copysign").symbolic_simplify(), parse("$x 0 copysign"));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn token_counts() {
+        let tokens = "3 $0 + 2 *".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.operand_count(), 2);
+        assert_eq!(expr.variable_count(), 1);
+        assert_eq!(expr.evaluator_count(), 2);
+        assert_eq!(expr.len(), 5);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn max_stack_depth() {
+        let expr = IntExpr::<i32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        assert_eq!(expr.max_stack_depth(), 2);
+
+        let expr = IntExpr::<i32>::from_iter("1 2 3 4 + + +".split_whitespace()).unwrap();
+        assert_eq!(expr.max_stack_depth(), 4);
+        assert_eq!(expr.depth_profile().into_iter().max(), Some(expr.max_stack_depth()));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn evaluate_with_stack() {
+        use convert_ref::TryFromRef;
+        use stack::Stack;
+
+        let tokens = "$0 $1 + 2 *".split_whitespace();
+        let expr = VariableIntExpr::<i64, IndexVar>::from_iter(tokens).unwrap();
+        let mut stack = Stack::with_capacity(expr.max_stack_depth());
+        for i in 0..1_000_000 {
+            assert_eq!(expr.evaluate_with_stack(&[i, 1], &mut stack), Ok((i + 1) * 2));
+        }
+
+        stack.push(42);
+        let missing = IndexVar::try_from_ref(&"$1").unwrap();
+        let res = expr.evaluate_with_stack(&[1], &mut stack);
+        assert_eq!(res, Err(EvalErr::VariableNotFound(missing)));
+        assert_eq!(expr.evaluate_with_stack(&[1, 2], &mut stack), Ok(6));
+        assert!(stack.is_empty());
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn evaluate_collecting_errors() {
+        use evaluate::IntEvaluateErr;
+
+        let tokens = "1 0 / $0 0 / + $1 +".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+
+        let (result, errors) = expr.evaluate_collecting_errors(&[5], 0);
+        assert_eq!(result, 0);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], (2, EvalErr::EvalError(IntEvaluateErr::InvalidDiv(1, 0))));
+        assert_eq!(errors[1], (5, EvalErr::EvalError(IntEvaluateErr::InvalidDiv(5, 0))));
+        assert_eq!(errors[2].0, 7);
+
+        let tokens = "$0 $1 +".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_collecting_errors(&[3, 4], 0), (7, Vec::new()));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn to_polish_prefix() {
+        let parse = |expr: &str| FloatExpr::<f32>::from_iter(expr.split_whitespace()).unwrap();
+        assert_eq!(parse("3 4 +").to_polish_prefix(), Some("+ 3 4".to_string()));
+        assert_eq!(parse("3 4 + 2 *").to_polish_prefix(), Some("* + 3 4 2".to_string()));
+        assert_eq!(parse("9 sqrt").to_polish_prefix(), Some("sqrt 9".to_string()));
+        assert_eq!(parse("2 3 4 - /").to_polish_prefix(), Some("/ 2 - 3 4".to_string()));
+        assert_eq!(parse("3 4 swap -").to_polish_prefix(), None);
+    }
+
+    #[cfg(all(feature = "float-evaluator", feature = "int-evaluator"))]
+    #[test]
+    fn to_string_with_sep() {
+        let expr = IntExpr::<i32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        assert_eq!(expr.to_string_with_sep(","), "3,4,+");
+        assert_eq!(expr.to_string_with_sep("\n"), "3\n4\n+");
+        assert_eq!(expr.to_string_with_sep(" "), expr.to_string());
+
+        let expr = FloatExpr::<f32>::from_iter("3.14159 2 *".split_whitespace()).unwrap();
+        assert_eq!(format!("{:.2}", expr), "3.14 2.00 *");
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn rewrite() {
+        use evaluate::VariableFloatExprNamed;
+        use expression::{RewriteRule, RuleVar, WildcardVar};
+
+        let pattern = vec![Arithm::Operand(0.0), Arithm::Evaluator(FloatEvaluator::Add)];
+        let rules = [RewriteRule::new(pattern, vec![])];
+        let expr = FloatExpr::<f32>::from_iter("5.0 0.0 +".split_whitespace()).unwrap();
+        assert_eq!(expr.rewrite(&rules).to_string(), "5");
+
+        let parse = |expr: &str| {
+            VariableFloatExprNamed::<f64>::from_iter(expr.split_whitespace()).unwrap()
+        };
+        let rules = [
+            RewriteRule::from_expressions(parse("$a $a -"), parse("0")),
+            RewriteRule::from_expressions(parse("$a 1 *"), parse("$a")),
+            RewriteRule::from_expressions(parse("$a $b swap -"), parse("$b $a -")),
+        ];
+        assert_eq!(parse("$x 2 + $x 2 + - 1 *").rewrite(&rules), parse("0"));
+        assert_eq!(parse("$x 2 + $x 3 + -").rewrite(&rules), parse("$x 2 + $x 3 + -"));
+        assert_eq!(parse("$x $y 1 * swap -").rewrite(&rules), parse("$y $x -"));
+
+        let pattern = vec![Arithm::Operand(2.0), Arithm::Evaluator(FloatEvaluator::Mul)];
+        let ignored = [RewriteRule::new(pattern, vec![Arithm::Operand(2.0)])];
+        assert_eq!(parse("$x 2 *").rewrite(&ignored), parse("$x 2 *"));
+
+        let x = parse("$x").token_at(0).and_then(Arithm::as_variable).cloned().unwrap();
+        let literal = [RewriteRule::new(
+            vec![
+                Arithm::Variable(RuleVar::Var(x)),
+                Arithm::Variable(RuleVar::Wildcard(WildcardVar(0))),
+                Arithm::Evaluator(FloatEvaluator::Mul),
+            ],
+            vec![Arithm::Variable(RuleVar::Wildcard(WildcardVar(0)))],
+        )];
+        assert_eq!(parse("$x 2 3 + *").rewrite(&literal), parse("2 3 +"));
+        assert_eq!(parse("$y 2 3 + *").rewrite(&literal), parse("$y 2 3 + *"));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn structural_eq() {
+        let expr = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let same = FloatExpr::<f32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let swapped = FloatExpr::<f32>::from_iter("4 3 +".split_whitespace()).unwrap();
+        let longer = FloatExpr::<f32>::from_iter("3 4 + 0 +".split_whitespace()).unwrap();
+
+        assert!(expr.structural_eq(&same));
+        assert!(!expr.structural_eq(&swapped));
+        assert!(!expr.structural_eq(&longer));
+        assert_eq!(expr.evaluate(), longer.evaluate());
+    }
+
+    #[cfg(all(feature = "float-evaluator", feature = "int-evaluator"))]
+    #[test]
+    fn token_stream() {
+        use expression::TokenStream;
+
+        let mut tokens = TokenStream::split_whitespace("rpn 3 4 + 2 *");
+        assert_eq!(tokens.peek(), Some("rpn"));
+        assert_eq!(tokens.position(), 0);
+        assert_eq!(tokens.next(), Some("rpn"));
+        assert_eq!(tokens.position(), 1);
+        let expr = FloatExpr::<f32>::from_token_stream(&mut tokens).unwrap();
+        assert_eq!(expr.evaluate(), Ok(14.0));
+        assert_eq!(tokens.peek(), None);
+        assert_eq!(tokens.position(), 6);
+
+        let mut tokens = TokenStream::new(vec!["rpn", "3", "&"]);
+        tokens.next();
+        match IntExpr::<i32>::from_token_stream(&mut tokens) {
+            Err(ExprResult::InvalidToken { position: 2, .. }) => (),
+            res => panic!("{:?}", res),
+        }
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn stack_statistics() {
+        use expression::StackStats;
+
+        let expr = IntExpr::<i32>::from_iter("1 2 3 + +".split_whitespace()).unwrap();
+        let expected = StackStats { min_depth: 1, max_depth: 3, total_pushes: 5, total_pops: 4 };
+        assert_eq!(expr.stack_statistics(), expected);
+        assert_eq!(expr.stack_statistics().max_depth, expr.max_stack_depth());
+
+        let expr = IntExpr::<i32>::from_iter("1 2 + 3 swap -".split_whitespace()).unwrap();
+        let expected = StackStats { min_depth: 1, max_depth: 2, total_pushes: 7, total_pops: 6 };
+        assert_eq!(expr.stack_statistics(), expected);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn evaluate_batch() {
+        let tokens = "$0 $1 /".split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let sets = vec![vec![1.0, 2.0], vec![9.0, 3.0], vec![1.0]];
+        let results = expr.evaluate_batch(&sets);
+        assert_eq!(results[0], Ok(0.5));
+        assert_eq!(results[1], Ok(3.0));
+        assert!(results[2].is_err());
+    }
+
+    #[cfg(all(feature = "rayon", feature = "float-evaluator"))]
+    #[test]
+    fn par_evaluate_batch() {
+        let tokens = "$0 $1 * $2 +".split_whitespace();
+        let expr = VariableFloatExpr::<f64, IndexVar>::from_iter(tokens).unwrap();
+        let sets: Vec<_> = (0..1000).map(|i| {
+            let i = i as f64;
+            vec![i, i / 2.0, 3.0]
+        }).collect();
+        assert_eq!(expr.par_evaluate_batch(&sets), expr.evaluate_batch(&sets));
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn compile_expression() {
+        let tokens = "$0 $1 / 2 +".split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let mut compiled = expr.clone().compile();
+        let sets = vec![vec![1.0, 2.0], vec![9.0, 3.0], vec![1.0], vec![4.0, 8.0]];
+        for variables in &sets {
+            assert_eq!(compiled(variables), expr.evaluate_with_variables(variables));
+        }
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn count_operations() {
+        use evaluate::Evaluate;
+
+        let expr = IntExpr::<i32>::from_iter("3 4 + 2 * 1 +".split_whitespace()).unwrap();
+        let counts = expr.count_operations();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["+"], 2);
+        assert_eq!(counts["*"], 1);
+
+        let counts = expr.count_operations_by(|evaluator| evaluator.operands_needed());
+        assert_eq!(counts.into_iter().collect::<Vec<_>>(), vec![(2, 3)]);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn constant_expression() {
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(vec!["3", "4", "+"]).unwrap();
+        assert!(expr.is_constant());
+
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(vec!["3", "$0", "+"]).unwrap();
+        assert!(!expr.is_constant());
+
+        let tokens = "3 $0 + $0 *".split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.variable_count(), 2);
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn evaluate_with_fallback() {
+        use convert_ref::TryFromRef;
+        use expression::EvalErr;
+
+        let tokens = "$0 $1 + $2 *".split_whitespace();
+        let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+        let variables = vec![3.0, 4.0];
+        assert_eq!(expr.evaluate_with_fallback(&variables, 2.0), Ok(14.0));
+        assert_eq!(expr.evaluate_with_fallback(&variables[..1], 0.0), Ok(0.0));
+        assert_eq!(expr.evaluate_with_variables(&variables),
+                   Err(EvalErr::VariableNotFound(IndexVar::try_from_ref(&"$2").unwrap())));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn evaluate_steps() {
+        use evaluate::IntEvaluateErr;
+        use expression::EvalStep;
+
+        let tokens = "3 $0 + 2 *".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        let variables = vec![4];
+        let mut steps = expr.evaluate_steps(&variables);
+        assert_eq!(steps.next(), Some(EvalStep::PushedOperand(3)));
+        assert_eq!(steps.next(), Some(EvalStep::PushedVariable(4)));
+        assert_eq!(steps.current_stack(), &[3, 4]);
+        assert_eq!(steps.next(), Some(EvalStep::EvaluatorResult(Ok(()))));
+        assert_eq!(steps.current_stack(), &[7]);
+        assert_eq!(steps.next(), Some(EvalStep::PushedOperand(2)));
+        assert_eq!(steps.next(), Some(EvalStep::EvaluatorResult(Ok(()))));
+        assert_eq!(steps.next(), Some(EvalStep::Finished(14)));
+        assert_eq!(steps.next(), None);
+
+        let empty: Vec<i32> = Vec::new();
+        let steps: Vec<_> = expr.evaluate_steps(&empty).collect();
+        let var = expr.variables().next().cloned().unwrap();
+        assert_eq!(steps, vec![EvalStep::PushedOperand(3), EvalStep::VariableNotFound(var)]);
+
+        let tokens = "3 0 / 2 *".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        let last = expr.evaluate_steps(&variables).last();
+        assert_eq!(last, Some(EvalStep::EvaluatorResult(Err(IntEvaluateErr::InvalidDiv(3, 0)))));
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn evaluate_with_step_callback() {
+        let tokens = "3 $0 + 2 * $1 swap -".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        let mut depths = Vec::new();
+        let mut indexes = Vec::new();
+        let res = expr.evaluate_with_step_callback(&vec![4, 20], |index, _, stack| {
+            indexes.push(index);
+            depths.push(stack.len());
+        });
+        assert_eq!(res, Ok(6));
+        assert_eq!(indexes, (0..expr.len()).collect::<Vec<_>>());
+
+        let mut expected = vec![0];
+        expected.extend(expr.depth_profile());
+        expected.pop();
+        assert_eq!(depths, expected);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn to_tokens() {
+        let expr = IntExpr::<i32>::from_iter("3 4 +".split_whitespace()).unwrap();
+        let tokens = expr.to_tokens();
+        assert_eq!(tokens, vec!["3", "4", "+"]);
+
+        let parsed = IntExpr::<i32>::from_iter(tokens.iter().map(|s| s.as_str())).unwrap();
+        assert_eq!(parsed, expr);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn evaluate_trace() {
+        let expr = IntExpr::<i32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        let (result, snapshots) = expr.evaluate_trace().unwrap();
+        assert_eq!(result, 14);
+        assert_eq!(snapshots, vec![vec![3], vec![3, 4], vec![7], vec![7, 2], vec![14]]);
+    }
+
+    #[cfg(feature = "int-evaluator")]
+    #[test]
+    fn depth_profile() {
+        let expr = IntExpr::<i32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+        assert_eq!(expr.depth_profile(), vec![1, 2, 1, 2, 1]);
+
+        let expr_str = "3 4 + 2 * 5 1 - /";
+        let expr = IntExpr::<i32>::from_iter(expr_str.split_whitespace()).unwrap();
+        assert_eq!(expr.depth_profile(), vec![1, 2, 1, 2, 1, 2, 3, 2, 1]);
+        assert_eq!(expr.depth_profile().len(), expr.len());
+    }
+
+    #[cfg(feature = "float-evaluator")]
+    #[test]
+    fn parse_error_as_boxed_error() {
+        use std::error::Error;
+
+        fn parse(expr_str: &str) -> Result<f32, Box<dyn Error + '_>> {
+            let expr = FloatExpr::<f32>::from_iter(expr_str.split_whitespace())?;
+            Ok(expr.evaluate()?)
+        }
+
+        assert_eq!(parse("3 4 +").unwrap(), 7.0);
+        let err = parse("3 4 &").unwrap_err();
+        assert!(err.to_string().contains("unknown float operator: '&'"));
+        let err = parse("3 4").unwrap_err();
+        assert_eq!(err.to_string(), "too many operands left on the stack (2 found)");
     }
 }
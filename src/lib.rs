@@ -26,23 +26,27 @@
 //! Ripin can evaluate `floating-point` expressions:
 //!
 //! ```rust
+//! # #[cfg(feature = "float-evaluator")] {
 //! # let expr = "3 4 + 2 *";
 //! # let tokens = expr.split_whitespace();
 //! use ripin::evaluate::FloatExpr;
 //!
 //! let expr = FloatExpr::<f32>::from_iter(tokens).unwrap();
 //! assert_eq!(expr.evaluate(), Ok(14.0));
+//! # }
 //! ```
 //!
 //! like `integers` ones:
 //!
 //! ```rust
+//! # #[cfg(feature = "int-evaluator")] {
 //! # let expr = "3 4 + 2 *";
 //! # let tokens = expr.split_whitespace();
 //! use ripin::evaluate::IntExpr;
 //!
 //! let expr = IntExpr::<i32>::from_iter(tokens).unwrap();
 //! assert_eq!(expr.evaluate(), Ok(14));
+//! # }
 //! ```
 //!
 //! # Variable Expression Usage
@@ -59,6 +63,7 @@
 //! Once variables as been set, do the same as before:
 //!
 //! ```rust
+//! # #[cfg(feature = "float-evaluator")] {
 //! # let variables = vec![3.0, 500.0];
 //! use ripin::evaluate::VariableFloatExpr;
 //! use ripin::variable::IndexVar;
@@ -67,11 +72,13 @@
 //!
 //! let tokens = expr.split_whitespace();
 //! let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
+//! # }
 //! ```
 //!
 //! Evaluate the expression with informations about the way of indexation (`usize`):
 //!
 //! ```rust
+//! # #[cfg(feature = "float-evaluator")] {
 //! # let variables = vec![3.0, 500.0];
 //! # use ripin::evaluate::VariableFloatExpr;
 //! # use ripin::variable::IndexVar;
@@ -79,8 +86,39 @@
 //! # let tokens = expr.split_whitespace();
 //! # let expr = VariableFloatExpr::<f32, IndexVar>::from_iter(tokens).unwrap();
 //! assert_eq!(expr.evaluate_with_variables(&variables), Ok(11.0));
+//! # }
 //! ```
 //!
+//! # Serialization
+//!
+//! Enable the `serde` feature to serialize expressions
+//! as the list of their string tokens (cf. `["3", "4", "+"]`).
+//! The same JSON format is available without `serde` with `to_json()` and `from_json()`.
+//!
+//! # `no_std` support
+//!
+//! The `std` feature is enabled by default, disable it to build the crate
+//! with `#![no_std]` and `alloc` only, the `HashMap` variable containers
+//! are then not available and float functions are computed by `libm`.
+//!
+//! # Evaluator features
+//!
+//! The `float-evaluator` and `int-evaluator` features are enabled by default,
+//! disable one of them to remove the corresponding [`Floats`] or integer
+//! evaluators (signed, unsigned and wrapping) and their expression aliases.
+//! Enable the `libm` feature to add the `gamma`, `lgamma`, `erf` and `erfc`
+//! operators to the [`Floats`] evaluator.
+//!
+//! # Parallel evaluation
+//!
+//! Enable the `rayon` feature to evaluate an expression
+//! over many variable sets in parallel with `par_evaluate_batch()`.
+//!
+//! # Minimum Rust version
+//!
+//! The crate needs Rust 1.86 or later, the `nextup` and `nextdown` float
+//! operators are computed by `f32::next_up()` and `f64::next_up()`.
+//!
 //! [`Reverse Polish Notated`]: https://en.wikipedia.org/wiki/Reverse_Polish_notation
 //! [`str`]: https://doc.rust-lang.org/std/str/index.html
 //! [`Floats`]: evaluate/enum.FloatEvaluator.html
@@ -88,10 +126,33 @@
 //! [`Evaluate`]: evaluate/trait.Evaluate.html
 //! [`Operand`]: expression/enum.Arithm.html
 
-extern crate num;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[macro_use]
+extern crate alloc;
+extern crate num_traits;
+extern crate num_complex;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "libm")]
+extern crate libm;
+
+#[macro_use]
+#[doc(hidden)]
+pub mod macros;
+
+use core::error::Error;
+use core::fmt;
 
 mod stack;
 
+#[cfg(feature = "serde")]
+mod serialize;
+
 /// TryFrom/Into_ref conversion module
 pub mod convert_ref;
 
@@ -104,15 +165,51 @@ pub mod variable;
 /// `Evaluate Trait` and default `Evaluators`.
 pub mod evaluate;
 
+/// Conversions between infix and `RPN` notations.
+pub mod convert;
+
+/// Explicit tree representation of expressions.
+pub mod ast;
+
+/// Symbolic differentiation of float expressions.
+#[cfg(feature = "float-evaluator")]
+pub mod calculus;
+
+/// Checking the types of the operands given to evaluators before evaluation.
+pub mod typecheck;
+
+/// Re-exports the types and traits needed most of the time.
+pub mod prelude;
+
 pub use stack::Stack;
 
+/// Type returned when a stack doesn't contain enough operands.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StackUnderflowError;
+
+impl fmt::Display for StackUnderflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("not enough operands on the stack")
+    }
+}
+
+impl Error for StackUnderflowError {}
+
 /// Removes the last two elements from a stack and return them,
-/// or `None` if there is not enough element.
-pub fn pop_two_operands<T>(stack: &mut Stack<T>) -> Option<(T, T)> {
+/// or a [`StackUnderflowError`] if there is not enough element.
+///
+/// [`StackUnderflowError`]: struct.StackUnderflowError.html
+pub fn pop_two_operands<T>(stack: &mut Stack<T>) -> Result<(T, T), StackUnderflowError> {
     if stack.len() >= 2 {
         let (a, b) = (stack.pop().unwrap(), stack.pop().unwrap());
-        Some((b, a))
+        Ok((b, a))
     } else {
-        None
+        Err(StackUnderflowError)
     }
 }
+
+/// Removes the last two elements from a stack and return them,
+/// or `None` if there is not enough element.
+pub fn try_pop_two_operands<T>(stack: &mut Stack<T>) -> Option<(T, T)> {
+    pop_two_operands(stack).ok()
+}
@@ -0,0 +1,178 @@
+/// Builds a [`FloatExpr<f64>`] from unquoted tokens, the operands balance
+/// is checked at compile time.
+///
+/// ```
+/// # #[macro_use] extern crate ripin;
+/// # fn main() {
+/// let expr = rpn_float!(3.0 4.0 + 2 * half pow);
+/// assert_eq!(expr.evaluate(), Ok(14.0_f64.sqrt()));
+/// # }
+/// ```
+///
+/// Expressions that do not leave exactly one value on the stack are rejected:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate ripin;
+/// # fn main() {
+/// let expr = rpn_float!(3.0 +);
+/// # }
+/// ```
+///
+/// Operands must be integer or float literals without suffix:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate ripin;
+/// # fn main() {
+/// let expr = rpn_float!("3" 4 +);
+/// # }
+/// ```
+///
+/// Tokens are separated by the Rust tokenizer, a `-` is always the substraction
+/// so negative numbers must be written with `neg` (cf. `2 neg`), `-one` is not available.
+/// Operators of the `libm` feature are not supported.
+///
+/// [`FloatExpr<f64>`]: evaluate/type.FloatExpr.html
+#[cfg(feature = "float-evaluator")]
+#[macro_export]
+macro_rules! rpn_float {
+    (@arity +) => { (2, 1) };
+    (@arity -) => { (2, 1) };
+    (@arity *) => { (2, 1) };
+    (@arity /) => { (2, 1) };
+    (@arity %) => { (2, 1) };
+    (@arity pow) => { (2, 1) };
+    (@arity lrelu) => { (2, 1) };
+    (@arity copysign) => { (2, 1) };
+    (@arity swap) => { (2, 2) };
+    (@arity neg) => { (1, 1) };
+    (@arity sqrt) => { (1, 1) };
+    (@arity log2) => { (1, 1) };
+    (@arity exp) => { (1, 1) };
+    (@arity round) => { (1, 1) };
+    (@arity sigmoid) => { (1, 1) };
+    (@arity relu) => { (1, 1) };
+    (@arity smoothstep) => { (1, 1) };
+    (@arity smootherstep) => { (1, 1) };
+    (@arity smoothstep3) => { (3, 1) };
+    (@arity nextup) => { (1, 1) };
+    (@arity nextdown) => { (1, 1) };
+    (@arity zero) => { (0, 1) };
+    (@arity one) => { (0, 1) };
+    (@arity two) => { (0, 1) };
+    (@arity half) => { (0, 1) };
+    (@arity $operand:literal) => { $crate::macros::operand_arity(stringify!($operand)) };
+    (@arity $token:tt) => {
+        compile_error!(concat!("rpn_float!: unknown float operator `", stringify!($token), "`"))
+    };
+    ($($token:tt)+) => {{
+        const _: () = $crate::macros::check_operands(&[$($crate::rpn_float!(@arity $token)),+]);
+        let tokens = [$(stringify!($token)),+];
+        $crate::evaluate::FloatExpr::<f64>::from_iter(tokens.iter().cloned())
+            .expect("rpn_float!: invalid float literal")
+    }};
+}
+
+/// Returns the arity of an operand token, in a constant context,
+/// panics if it is not a number literal parsable as a float.
+#[cfg(feature = "float-evaluator")]
+pub const fn operand_arity(literal: &str) -> (usize, usize) {
+    if !is_float_literal(literal) {
+        panic!("rpn_float!: operands must be number literals without suffix nor underscore")
+    }
+    (0, 1)
+}
+
+/// Returns `true` if the literal is made of digits with an optional
+/// fractional part and exponent (e.g. `3`, `4.5` or `1e3`).
+#[cfg(feature = "float-evaluator")]
+const fn is_float_literal(literal: &str) -> bool {
+    let bytes = literal.as_bytes();
+    let mut i = 0;
+    let digits_start = i;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    let mut valid = i > digits_start;
+    if valid && i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    if valid && i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        i += 1;
+        if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+            i += 1;
+        }
+        let exponent_start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        valid = i > exponent_start;
+    }
+    valid && i == bytes.len()
+}
+
+/// Checks, in a constant context, that the tokens described by their
+/// number of operands needed and generated leave exactly one value on the stack.
+#[cfg(feature = "float-evaluator")]
+pub const fn check_operands(arities: &[(usize, usize)]) {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < arities.len() {
+        let (needed, generated) = arities[i];
+        if depth < needed {
+            panic!("rpn_float!: not enough operands for an operator")
+        }
+        depth = depth - needed + generated;
+        i += 1;
+    }
+    if depth != 1 {
+        panic!("rpn_float!: the expression must leave exactly one value on the stack")
+    }
+}
+
+#[cfg(all(test, feature = "float-evaluator"))]
+mod tests {
+    #[test]
+    fn rpn_float() {
+        let expr = rpn_float!(3.0 4.0 +);
+        assert_eq!(expr.evaluate(), Ok(7.0_f64));
+
+        let expr = rpn_float!(3 4 swap - 2 neg * one %);
+        assert_eq!(expr.evaluate(), Ok(0.0));
+        assert_eq!(expr.to_string(), "3 4 swap - 2 neg * one %");
+
+        let expr = rpn_float!(1e3 2.5 + 10. *);
+        assert_eq!(expr.evaluate(), Ok(10025.0));
+    }
+
+    #[test]
+    fn arities_match_the_evaluator() {
+        use convert_ref::TryFromRef;
+        use evaluate::{Evaluate, FloatEvaluator};
+
+        macro_rules! check_arities {
+            ($($token:tt)+) => {$(
+                let evaluator = FloatEvaluator::<f64>::try_from_ref(&stringify!($token)).unwrap();
+                let arity = (evaluator.operands_needed(), evaluator.operands_generated());
+                assert_eq!(rpn_float!(@arity $token), arity, "{}", stringify!($token));
+            )+};
+        }
+
+        check_arities!(+ - * / % pow lrelu copysign swap neg sqrt log2 exp round sigmoid relu
+                       smoothstep smootherstep smoothstep3 nextup nextdown zero one two half);
+    }
+
+    #[test]
+    fn float_literals() {
+        use macros::is_float_literal;
+
+        for literal in &["3", "3.", "3.25", "1e3", "1.5E-3", "2e+8"] {
+            assert!(is_float_literal(literal), "{}", literal);
+        }
+        for literal in &["\"3\"", "true", "'a'", "1_000", "3f32", "0x10", "1e", ".5"] {
+            assert!(!is_float_literal(literal), "{}", literal);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! A single wildcard import is enough to parse and evaluate expressions.
+//!
+//! ```
+//! # #[cfg(all(feature = "float-evaluator", feature = "int-evaluator"))] {
+//! use ripin::prelude::*;
+//!
+//! let expr = FloatExpr::<f32>::from_iter("3 4 + 2 *".split_whitespace()).unwrap();
+//! assert_eq!(expr.evaluate(), Ok(14.0));
+//!
+//! let expr = VariableIntExpr::<i32, IndexVar>::from_iter("$0 $1 +".split_whitespace()).unwrap();
+//! assert_eq!(expr.evaluate_with_variables(&vec![3, 4]), Ok(7));
+//!
+//! let evaluator: FloatEvaluator<f32> = TryFromRef::try_from_ref(&"sqrt").unwrap();
+//! assert_eq!(evaluator.operands_needed(), 1);
+//! # }
+//! ```
+
+pub use expression::{Expression, Arithm, ExprResult, OperandErr};
+pub use evaluate::Evaluate;
+#[cfg(feature = "float-evaluator")]
+pub use evaluate::{FloatEvaluator, FloatExpr, VariableFloatExpr};
+#[cfg(feature = "int-evaluator")]
+pub use evaluate::{IntEvaluator, IntExpr, VariableIntExpr};
+pub use variable::{IndexVar, GetVariable, DummyVariable, DummyVariables};
+pub use convert_ref::{TryFromRef, TryIntoRef};
+pub use stack::Stack;
@@ -0,0 +1,75 @@
+use core::fmt;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::ser::{Serialize, Serializer, SerializeSeq};
+use serde::de::{self, Deserialize, Deserializer};
+use expression::{Arithm, Expression};
+use evaluate::Evaluate;
+use convert_ref::TryFromRef;
+
+impl<T, V, E> Serialize for Arithm<T, V, E>
+    where T: fmt::Display,
+          V: fmt::Display,
+          E: fmt::Display + Evaluate<T>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Arithm::Operand(ref operand) => serializer.collect_str(operand),
+            Arithm::Variable(ref variable) => serializer.collect_str(variable),
+            Arithm::Evaluator(ref evaluator) => serializer.collect_str(evaluator),
+        }
+    }
+}
+
+impl<T, V, E> Serialize for Expression<T, V, E>
+    where T: fmt::Display,
+          V: fmt::Display,
+          E: fmt::Display + Evaluate<T>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for arithm in self {
+            seq.serialize_element(arithm)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T, V, E> Deserialize<'de> for Expression<T, V, E>
+    where E: Evaluate<T>,
+          for<'a> T: TryFromRef<&'a str>,
+          for<'a> V: TryFromRef<&'a str>,
+          for<'a> E: TryFromRef<&'a str>,
+          for<'a> <T as TryFromRef<&'a str>>::Err: fmt::Debug,
+          for<'a> <V as TryFromRef<&'a str>>::Err: fmt::Debug,
+          for<'a> <E as TryFromRef<&'a str>>::Err: fmt::Debug
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tokens = Vec::<String>::deserialize(deserializer)?;
+        Expression::from_iter(tokens.iter().map(String::as_str))
+            .map_err(|err| de::Error::custom(format!("invalid expression: {:?}", err)))
+    }
+}
+
+#[cfg(all(test, feature = "float-evaluator"))]
+mod tests {
+    extern crate serde_json;
+
+    use evaluate::FloatExpr;
+
+    #[test]
+    fn round_trip_float_expression() {
+        let expr_str = "3.5 4 + 2 * sqrt";
+        let expr = FloatExpr::<f64>::from_iter(expr_str.split_whitespace()).unwrap();
+        let json = serde_json::to_string(&expr).unwrap();
+        assert_eq!(json, r#"["3.5","4","+","2","*","sqrt"]"#);
+        let parsed: FloatExpr<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, expr);
+    }
+
+    #[test]
+    fn invalid_expression() {
+        let res = serde_json::from_str::<FloatExpr<f64>>(r#"["3", "&"]"#);
+        assert!(res.is_err());
+    }
+}
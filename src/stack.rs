@@ -1,3 +1,6 @@
+use core::ops::{Index, RangeFrom};
+use alloc::vec::Vec;
+
 /// A growable stack implementing `push/pop` actions.
 ///
 /// ```
@@ -15,6 +18,12 @@
 /// ```
 pub struct Stack<T>(Vec<T>);
 
+impl<T> Default for Stack<T> {
+    fn default() -> Stack<T> {
+        Stack::new()
+    }
+}
+
 impl<T> Stack<T> {
     /// Creates an empty VecDeque.
     ///
@@ -117,4 +126,115 @@ impl<T> Stack<T> {
     pub fn pop(&mut self) -> Option<T> {
         self.0.pop()
     }
+
+    /// Extracts a slice containing the entire stack,
+    /// from the bottom to the top.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ripin::Stack;
+    ///
+    /// let mut stack = Stack::new();
+    /// stack.push(3);
+    /// stack.push(2);
+    /// assert_eq!(stack.as_slice(), &[3, 2]);
+    /// ```
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Returns a slice of the `n` elements at the top of the stack,
+    /// from the bottom to the top, or `None` if there is less than `n` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ripin::Stack;
+    ///
+    /// let mut stack = Stack::new();
+    /// for i in 1..6 {
+    ///     stack.push(i);
+    /// }
+    /// assert_eq!(stack.peek_slice(3), Some(&[3, 4, 5][..]));
+    /// assert_eq!(stack.peek_slice(6), None);
+    /// assert_eq!(stack.len(), 5);
+    /// ```
+    #[inline]
+    pub fn peek_slice(&self, n: usize) -> Option<&[T]> {
+        self.0.len().checked_sub(n).map(|start| &self.0[start..])
+    }
+
+    /// Removes all the values from the stack,
+    /// keeping the allocated capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ripin::Stack;
+    ///
+    /// let mut stack = Stack::new();
+    /// stack.push(3);
+    /// stack.clear();
+    /// assert!(stack.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+/// Gives read access to any element of the stack,
+/// the index `0` being the bottom and `len() - 1` the top.
+///
+/// # Panics
+///
+/// Panics if the index is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use ripin::Stack;
+///
+/// let mut stack = Stack::new();
+/// stack.push(1);
+/// stack.push(2);
+/// stack.push(3);
+/// assert_eq!(stack[0], 1);
+/// assert_eq!(stack[2], 3);
+/// ```
+impl<T> Index<usize> for Stack<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        &self.0[index]
+    }
+}
+
+/// Gives read access to the elements of the stack from the given index to the top.
+///
+/// # Panics
+///
+/// Panics if the start of the range is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use ripin::Stack;
+///
+/// let mut stack = Stack::new();
+/// stack.push(1);
+/// stack.push(2);
+/// stack.push(3);
+/// assert_eq!(&stack[1..], &[2, 3]);
+/// ```
+impl<T> Index<RangeFrom<usize>> for Stack<T> {
+    type Output = [T];
+
+    #[inline]
+    fn index(&self, index: RangeFrom<usize>) -> &[T] {
+        &self.0[index]
+    }
 }
@@ -0,0 +1,274 @@
+use core::error::Error;
+use core::fmt;
+use stack::Stack;
+use evaluate::Evaluate;
+use expression::{Arithm, Expression};
+use variable::GetVariableOwned;
+
+/// Gives the type of an operand, `Tag` being any user-defined type description
+/// (cf. an enum with one variant for each kind of operand).
+pub trait TypeOf<Tag> {
+    /// Returns the type tag of this operand.
+    fn type_tag(&self) -> Tag;
+}
+
+/// An [`Evaluate`] extension describing the types of the operands an evaluator
+/// consumes and produces, allowing expressions to be checked before evaluation.
+///
+/// [`Evaluate`]: ../evaluate/trait.Evaluate.html
+pub trait EvaluateTyped<T, Tag>: Evaluate<T> {
+    /// Returns the types of the operands this `Evaluator` needs, from the bottom
+    /// to the top of the stack, its length must be [`operands_needed()`].
+    ///
+    /// [`operands_needed()`]: ../evaluate/trait.Evaluate.html#tymethod.operands_needed
+    fn input_types(&self) -> &[Tag];
+
+    /// Returns the types of the operands this `Evaluator` generates, from the bottom
+    /// to the top of the stack, its length must be [`operands_generated()`].
+    ///
+    /// [`operands_generated()`]: ../evaluate/trait.Evaluate.html#tymethod.operands_generated
+    fn output_types(&self) -> &[Tag];
+}
+
+/// Type returned when the types of an expression are not compatible.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeCheckError<V, Tag> {
+    /// The evaluator at `position` expected an operand of another type.
+    TypeMismatch { position: usize, expected: Tag, found: Tag },
+    /// The type of a variable is not known.
+    UnknownVariable(V),
+    /// The evaluator at `position` gives input or output types that don't match
+    /// the number of operands it needs or generates.
+    ArityMismatch { position: usize },
+}
+
+impl<V: fmt::Debug, Tag: fmt::Debug> fmt::Display for TypeCheckError<V, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TypeCheckError::TypeMismatch { position, ref expected, ref found } => {
+                write!(f, "evaluator at position {} expected {:?} but found {:?}",
+                       position, expected, found)
+            }
+            TypeCheckError::UnknownVariable(ref var) => {
+                write!(f, "type of variable {:?} is unknown", var)
+            }
+            TypeCheckError::ArityMismatch { position } => {
+                write!(f, "evaluator at position {} has types not matching its arity", position)
+            }
+        }
+    }
+}
+
+impl<V: fmt::Debug, Tag: fmt::Debug> Error for TypeCheckError<V, Tag> {}
+
+impl<T, V: Clone, E: Evaluate<T>> Expression<T, V, E> {
+    /// Checks that every evaluator receives operands of the types it expects,
+    /// the types of the variables are retrieved from `variable_types`
+    /// like variables values are retrieved for evaluation.
+    pub fn validate_types<Tag, I, M, C>(&self, variable_types: &C)
+                                        -> Result<(), TypeCheckError<V, Tag>>
+        where T: TypeOf<Tag>,
+              E: EvaluateTyped<T, Tag>,
+              Tag: PartialEq + Clone,
+              V: Into<I>,
+              C: GetVariableOwned<I, M, Output=Tag> + ?Sized
+    {
+        let mut stack = Stack::new();
+        for (position, arithm) in self.iter().enumerate() {
+            match *arithm {
+                Arithm::Operand(ref operand) => stack.push(operand.type_tag()),
+                Arithm::Variable(ref var) => {
+                    let tag = variable_types.get_variable_owned(var.clone().into())
+                        .ok_or_else(|| TypeCheckError::UnknownVariable(var.clone()))?;
+                    stack.push(tag)
+                }
+                Arithm::Evaluator(ref evaluator) => {
+                    let (inputs, outputs) = (evaluator.input_types(), evaluator.output_types());
+                    if inputs.len() != evaluator.operands_needed()
+                        || outputs.len() != evaluator.operands_generated() {
+                        return Err(TypeCheckError::ArityMismatch { position })
+                    }
+                    let start = stack.len().checked_sub(inputs.len())
+                        .ok_or(TypeCheckError::ArityMismatch { position })?;
+                    for (expected, found) in inputs.iter().zip(&stack[start..]) {
+                        if expected != found {
+                            return Err(TypeCheckError::TypeMismatch {
+                                position,
+                                expected: expected.clone(),
+                                found: found.clone(),
+                            })
+                        }
+                    }
+                    for _ in 0..inputs.len() {
+                        stack.pop();
+                    }
+                    for tag in outputs {
+                        stack.push(tag.clone())
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use convert_ref::TryFromRef;
+    use evaluate::Evaluate;
+    use expression::Expression;
+    use stack::Stack;
+    use typecheck::{EvaluateTyped, TypeCheckError, TypeOf};
+    use variable::IndexVar;
+    use ::pop_two_operands;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Tag {
+        Num,
+        Bool,
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Operand {
+        Num(i32),
+        Bool(bool),
+    }
+
+    impl TypeOf<Tag> for Operand {
+        fn type_tag(&self) -> Tag {
+            match *self {
+                Operand::Num(_) => Tag::Num,
+                Operand::Bool(_) => Tag::Bool,
+            }
+        }
+    }
+
+    impl<'a> TryFromRef<&'a str> for Operand {
+        type Err = ();
+        fn try_from_ref(s: &&'a str) -> Result<Self, Self::Err> {
+            match *s {
+                "true" => Ok(Operand::Bool(true)),
+                "false" => Ok(Operand::Bool(false)),
+                s => s.parse().map(Operand::Num).map_err(|_| ()),
+            }
+        }
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Evaluator {
+        Add,
+        Greater,
+        Not,
+        /// Declares no output type but generates an operand.
+        Drop,
+    }
+
+    impl Evaluate<Operand> for Evaluator {
+        type Err = ();
+
+        fn operands_needed(&self) -> usize {
+            self.input_types().len()
+        }
+
+        fn operands_generated(&self) -> usize {
+            1
+        }
+
+        #[allow(clippy::unit_arg)]
+        fn evaluate(self, stack: &mut Stack<Operand>) -> Result<(), Self::Err> {
+            match self {
+                Evaluator::Add => match pop_two_operands(stack).unwrap() {
+                    (Operand::Num(a), Operand::Num(b)) => Ok(stack.push(Operand::Num(a + b))),
+                    _ => Err(()),
+                },
+                Evaluator::Greater => match pop_two_operands(stack).unwrap() {
+                    (Operand::Num(a), Operand::Num(b)) => Ok(stack.push(Operand::Bool(a > b))),
+                    _ => Err(()),
+                },
+                Evaluator::Not => match stack.pop().unwrap() {
+                    Operand::Bool(a) => Ok(stack.push(Operand::Bool(!a))),
+                    _ => Err(()),
+                },
+                Evaluator::Drop => stack.pop().map(drop).ok_or(()),
+            }
+        }
+    }
+
+    impl EvaluateTyped<Operand, Tag> for Evaluator {
+        fn input_types(&self) -> &[Tag] {
+            match *self {
+                Evaluator::Add | Evaluator::Greater => &[Tag::Num, Tag::Num],
+                Evaluator::Not => &[Tag::Bool],
+                Evaluator::Drop => &[Tag::Num],
+            }
+        }
+
+        fn output_types(&self) -> &[Tag] {
+            match *self {
+                Evaluator::Add => &[Tag::Num],
+                Evaluator::Greater | Evaluator::Not => &[Tag::Bool],
+                Evaluator::Drop => &[],
+            }
+        }
+    }
+
+    impl<'a> TryFromRef<&'a str> for Evaluator {
+        type Err = ();
+        fn try_from_ref(s: &&'a str) -> Result<Self, Self::Err> {
+            match *s {
+                "+" => Ok(Evaluator::Add),
+                ">" => Ok(Evaluator::Greater),
+                "not" => Ok(Evaluator::Not),
+                "drop" => Ok(Evaluator::Drop),
+                _ => Err(()),
+            }
+        }
+    }
+
+    type TypedExpr = Expression<Operand, IndexVar, Evaluator>;
+
+    fn parse(expr: &str) -> TypedExpr {
+        TypedExpr::from_iter(expr.split_whitespace()).unwrap()
+    }
+
+    #[test]
+    fn valid_types() {
+        let expr = parse("1 $0 + 2 > not");
+        assert_eq!(expr.validate_types(&[Tag::Num]), Ok(()));
+        assert_eq!(expr.evaluate_with_variables(&[Operand::Num(3)]), Ok(Operand::Bool(false)));
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let expr = parse("1 2 + not");
+        let mismatch = TypeCheckError::TypeMismatch {
+            position: 3,
+            expected: Tag::Bool,
+            found: Tag::Num,
+        };
+        assert_eq!(expr.validate_types(&[Tag::Num]), Err(mismatch));
+
+        let expr = parse("1 $0 +");
+        let mismatch = TypeCheckError::TypeMismatch {
+            position: 2,
+            expected: Tag::Num,
+            found: Tag::Bool,
+        };
+        assert_eq!(expr.validate_types(&[Tag::Bool]), Err(mismatch));
+    }
+
+    #[test]
+    fn unknown_variable() {
+        let expr = parse("1 $1 +");
+        let var = IndexVar::try_from_ref(&"$1").unwrap();
+        assert_eq!(expr.validate_types(&[Tag::Num]), Err(TypeCheckError::UnknownVariable(var)));
+    }
+
+    #[test]
+    fn arity_mismatch() {
+        let expr = parse("1 2 drop +");
+        let mismatch = TypeCheckError::ArityMismatch { position: 2 };
+        assert_eq!(expr.validate_types(&[Tag::Num]), Err(mismatch.clone()));
+        assert_eq!(mismatch.to_string(), "evaluator at position 2 has types not matching its arity");
+    }
+}
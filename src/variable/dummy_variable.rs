@@ -1,4 +1,5 @@
-use std::fmt;
+use core::error::Error;
+use core::fmt;
 use convert_ref::TryFromRef;
 
 /// Struct that implement [`TryFromRef`] and always returns `Err`,
@@ -8,14 +9,28 @@ use convert_ref::TryFromRef;
 ///
 /// [`TryFromRef`]: ../convert_ref/trait.TryFromRef.html
 /// [`DummyVariables`]: ../variable/struct.DummyVariables.html
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct DummyVariable;
 
+/// Type returned by [`DummyVariable`] conversions, that always fail.
+///
+/// [`DummyVariable`]: ../variable/struct.DummyVariable.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DummyVariableErr;
+
+impl fmt::Display for DummyVariableErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("variables are not supported by this expression")
+    }
+}
+
+impl Error for DummyVariableErr {}
+
 impl<T> TryFromRef<T> for DummyVariable {
-    type Err = ();
+    type Err = DummyVariableErr;
 
     fn try_from_ref(_: &T) -> Result<Self, Self::Err> {
-        Err(())
+        Err(DummyVariableErr)
     }
 }
 
@@ -26,7 +41,5 @@ impl fmt::Display for DummyVariable {
 }
 
 impl From<DummyVariable> for () {
-    fn from(_: DummyVariable) -> Self {
-        ()
-    }
+    fn from(_: DummyVariable) -> Self {}
 }
@@ -1,5 +1,5 @@
-use std::default::Default;
-use std::marker::PhantomData;
+use core::default::Default;
+use core::marker::PhantomData;
 use variable::GetVariable;
 
 /// Struct that implement [`Index`],
@@ -15,7 +15,7 @@ pub struct DummyVariables<T>(PhantomData<T>);
 
 impl<T> Default for DummyVariables<T> {
     fn default() -> Self {
-        DummyVariables(PhantomData::default())
+        DummyVariables(PhantomData)
     }
 }
 
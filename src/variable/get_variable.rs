@@ -1,18 +1,80 @@
-use std::hash::Hash;
-use std::cmp::{Eq, Ord};
-use std::collections::{HashMap, VecDeque, LinkedList, BTreeMap};
+#[cfg(feature = "std")]
+use core::hash::Hash;
+use core::cmp::Ord;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+use alloc::collections::{VecDeque, LinkedList, BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use alloc::string::String;
 
 /// This trait allow [`Expression`] to retrieve variables
 /// from an from a container using an index variable like [`IndexVar`].
 ///
 /// [`Expression`]: ../expression/struct.Expression.html
-/// [`IndexVar`]: ../variable/struct.VarIdx.html
+/// [`IndexVar`]: ../variable/struct.IndexVar.html
 pub trait GetVariable<I> {
     type Output;
 
     fn get_variable(&self, index: I) -> Option<&Self::Output>;
 }
 
+/// Same as [`GetVariable`] but gives a mutable access to the variables,
+/// allowing the container to be updated between or during evaluations.
+///
+/// [`GetVariable`]: ../variable/trait.GetVariable.html
+pub trait GetVariableMut<I> {
+    type Output;
+
+    fn get_variable_mut(&mut self, index: I) -> Option<&mut Self::Output>;
+}
+
+/// Allows [`Expression`] to retrieve owned variables, this trait is implemented
+/// for any [`GetVariable`] container with a `Clone` output and for closures like
+/// `Fn(I) -> Option<T>`, the `M` marker type keeps these implementations apart.
+///
+/// [`Expression`]: ../expression/struct.Expression.html
+/// [`GetVariable`]: ../variable/trait.GetVariable.html
+pub trait GetVariableOwned<I, M> {
+    type Output;
+
+    fn get_variable_owned(&self, index: I) -> Option<Self::Output>;
+}
+
+/// Marker used by the [`GetVariableOwned`] implementation of [`GetVariable`] containers.
+///
+/// [`GetVariableOwned`]: ../variable/trait.GetVariableOwned.html
+/// [`GetVariable`]: ../variable/trait.GetVariable.html
+#[derive(Debug)]
+pub enum ByRef {}
+
+/// Marker used by the [`GetVariableOwned`] implementation of closures.
+///
+/// [`GetVariableOwned`]: ../variable/trait.GetVariableOwned.html
+#[derive(Debug)]
+pub enum ByFn {}
+
+impl<I, C> GetVariableOwned<I, ByRef> for C
+    where C: GetVariable<I> + ?Sized,
+          C::Output: Clone
+{
+    type Output = C::Output;
+
+    fn get_variable_owned(&self, index: I) -> Option<Self::Output> {
+        self.get_variable(index).cloned()
+    }
+}
+
+impl<I, T, F> GetVariableOwned<I, ByFn> for F
+    where F: Fn(I) -> Option<T>
+{
+    type Output = T;
+
+    fn get_variable_owned(&self, index: I) -> Option<Self::Output> {
+        self(index)
+    }
+}
+
+#[cfg(feature = "std")]
 impl<I: Hash + Eq, T> GetVariable<I> for HashMap<I, T> {
     type Output = T;
 
@@ -29,11 +91,75 @@ impl<I: Ord, T> GetVariable<I> for BTreeMap<I, T> {
     }
 }
 
+/// Retrieves the variables of string keyed maps by `&str`, without allocating a `String`,
+/// it is used by [`evaluate_with_str_variables()`].
+///
+/// This is not a [`GetVariable`] implementation for `String` keyed maps,
+/// a second index type would break the inference of the ones already used.
+///
+/// [`evaluate_with_str_variables()`]: ../expression/struct.Expression.html#method.evaluate_with_str_variables
+/// [`GetVariable`]: ../variable/trait.GetVariable.html
+pub trait GetVariableStr {
+    type Output;
+
+    fn get_variable_str(&self, name: &str) -> Option<&Self::Output>;
+}
+
+#[cfg(feature = "std")]
+impl<T> GetVariableStr for HashMap<String, T> {
+    type Output = T;
+
+    fn get_variable_str(&self, name: &str) -> Option<&Self::Output> {
+        self.get(name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> GetVariableStr for HashMap<&str, T> {
+    type Output = T;
+
+    fn get_variable_str(&self, name: &str) -> Option<&Self::Output> {
+        self.get(name)
+    }
+}
+
+impl<T> GetVariableStr for BTreeMap<String, T> {
+    type Output = T;
+
+    fn get_variable_str(&self, name: &str) -> Option<&Self::Output> {
+        self.get(name)
+    }
+}
+
+impl<T> GetVariableStr for BTreeMap<&str, T> {
+    type Output = T;
+
+    fn get_variable_str(&self, name: &str) -> Option<&Self::Output> {
+        self.get(name)
+    }
+}
+
 impl<T> GetVariable<usize> for Vec<T> {
     type Output = T;
 
     fn get_variable(&self, index: usize) -> Option<&Self::Output> {
-        self.iter().nth(index)
+        self.get(index)
+    }
+}
+
+impl<T> GetVariable<usize> for [T] {
+    type Output = T;
+
+    fn get_variable(&self, index: usize) -> Option<&Self::Output> {
+        self.get(index)
+    }
+}
+
+impl<T, const N: usize> GetVariable<usize> for [T; N] {
+    type Output = T;
+
+    fn get_variable(&self, index: usize) -> Option<&Self::Output> {
+        self.get(index)
     }
 }
 
@@ -52,3 +178,98 @@ impl<T> GetVariable<usize> for LinkedList<T> {
         self.iter().nth(index)
     }
 }
+
+#[cfg(feature = "std")]
+impl<I: Hash + Eq, T> GetVariableMut<I> for HashMap<I, T> {
+    type Output = T;
+
+    fn get_variable_mut(&mut self, index: I) -> Option<&mut Self::Output> {
+        self.get_mut(&index)
+    }
+}
+
+impl<I: Ord, T> GetVariableMut<I> for BTreeMap<I, T> {
+    type Output = T;
+
+    fn get_variable_mut(&mut self, index: I) -> Option<&mut Self::Output> {
+        self.get_mut(&index)
+    }
+}
+
+impl<T> GetVariableMut<usize> for Vec<T> {
+    type Output = T;
+
+    fn get_variable_mut(&mut self, index: usize) -> Option<&mut Self::Output> {
+        self.get_mut(index)
+    }
+}
+
+impl<T> GetVariableMut<usize> for VecDeque<T> {
+    type Output = T;
+
+    fn get_variable_mut(&mut self, index: usize) -> Option<&mut Self::Output> {
+        self.get_mut(index)
+    }
+}
+
+/// Variables index the elements of the set in ascending order:
+/// `$0` is the smallest element, `$1` the next one, etc.
+/// The lookup is linear in the index.
+impl<T> GetVariable<usize> for BTreeSet<T> {
+    type Output = T;
+
+    fn get_variable(&self, index: usize) -> Option<&Self::Output> {
+        self.iter().nth(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::{BTreeMap, BTreeSet};
+    #[cfg(feature = "std")]
+    use std::collections::HashMap;
+    use variable::{GetVariable, GetVariableStr};
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn hash_map_string_keys_with_str_lookup() {
+        let mut map = HashMap::new();
+        map.insert("x".to_string(), 3.0_f32);
+        assert_eq!(map.get_variable_str("x"), Some(&3.0));
+        assert_eq!(map.get_variable_str("y"), None);
+        assert_eq!(map.get_variable("x".to_string()), Some(&3.0));
+
+        let mut map = HashMap::new();
+        map.insert("x", 3.0_f32);
+        assert_eq!(map.get_variable_str("x"), Some(&3.0));
+        assert_eq!(map.get_variable("x"), Some(&3.0));
+    }
+
+    #[test]
+    fn btree_map_string_keys_with_str_lookup() {
+        let mut map = BTreeMap::new();
+        map.insert("x".to_string(), 3.0_f32);
+        assert_eq!(map.get_variable_str("x"), Some(&3.0));
+        assert_eq!(map.get_variable_str("y"), None);
+    }
+
+    #[test]
+    fn btree_set_sorted_indexing() {
+        let set: BTreeSet<_> = vec![15, 5, 10, 5].into_iter().collect();
+        assert_eq!(set.get_variable(0), Some(&5));
+        assert_eq!(set.get_variable(2), Some(&15));
+        assert_eq!(set.get_variable(3), None);
+    }
+
+    #[test]
+    #[cfg(feature = "int-evaluator")]
+    fn btree_set_variables() {
+        use evaluate::VariableIntExpr;
+        use variable::IndexVar;
+
+        let set: BTreeSet<_> = vec![10, 15, 5].into_iter().collect();
+        let tokens = "$1 $0 -".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_with_variables(&set), Ok(5));
+    }
+}
@@ -1,5 +1,7 @@
-use std::convert::From;
-use std::str::FromStr;
+use core::convert::From;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
 use convert_ref::TryFromRef;
 
 /// Default variable keeping an index on an [`Indexable`] variable container.
@@ -8,15 +10,33 @@ use convert_ref::TryFromRef;
 /// [`Indexable`]: https://doc.rust-lang.org/std/ops/trait.Index.html
 /// [`Vec`]: https://doc.rust-lang.org/std/vec/struct.Vec.html
 /// [`hashMap`]: https://doc.rust-lang.org/nightly/std/collections/struct.HashMap.html
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct IndexVar(usize);
 
+/// Former name of [`IndexVar`], kept for backward compatibility.
+///
+/// [`IndexVar`]: ../variable/struct.IndexVar.html
+#[deprecated(note = "use `IndexVar` instead")]
+pub type VarIdx = IndexVar;
+
+/// Type returned when a token is not a valid index variable.
 #[derive(Debug)]
 pub enum VarIdxErr<'a, E> {
     InvalidVariableName(&'a str),
     ConvertErr(E),
 }
 
+impl<'a, E: fmt::Display> fmt::Display for VarIdxErr<'a, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VarIdxErr::InvalidVariableName(name) => write!(f, "invalid variable name: '{}'", name),
+            VarIdxErr::ConvertErr(ref err) => write!(f, "invalid variable index: {}", err),
+        }
+    }
+}
+
+impl<'a, E: fmt::Debug + fmt::Display> Error for VarIdxErr<'a, E> {}
+
 impl<'a> TryFromRef<&'a str> for IndexVar {
     type Err = VarIdxErr<'a, <usize as FromStr>::Err>;
 
@@ -33,6 +53,12 @@ impl<'a> TryFromRef<&'a str> for IndexVar {
     }
 }
 
+impl fmt::Display for IndexVar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
 impl From<IndexVar> for usize {
     fn from(var_idx: IndexVar) -> Self {
         var_idx.0
@@ -0,0 +1,147 @@
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+use alloc::collections::BTreeMap;
+use variable::{GetVariable, GetVariableOwned};
+
+/// A variable value computed on demand, only if an evaluation retrieves it
+/// through [`LazyVariables`] (cf. a database lookup).
+///
+/// [`LazyVariables`]: ../variable/struct.LazyVariables.html
+pub struct LazyVariable<T, F: Fn() -> T> {
+    init: F,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, F: Fn() -> T> LazyVariable<T, F> {
+    /// Creates a variable whose value will be computed by `init`.
+    pub fn new(init: F) -> Self {
+        LazyVariable { init, _marker: PhantomData }
+    }
+
+    /// Computes the value, calling `init` each time.
+    pub fn compute(&self) -> T {
+        (self.init)()
+    }
+}
+
+/// Variable container retrieving the values of the [`LazyVariable`]s
+/// stored in any [`GetVariable`] container, like a `Vec` or a `HashMap`.
+///
+/// Each value is computed at most once and cached by this container, keyed by the index
+/// of the variable, a new one must be created for each evaluation to start with an empty cache.
+/// With the `std` feature the cache is behind a `Mutex`, the container can then be shared
+/// between threads (cf. `par_evaluate_batch`).
+///
+/// ```
+/// # #[cfg(feature = "int-evaluator")] {
+/// use ripin::evaluate::VariableIntExpr;
+/// use ripin::variable::{IndexVar, LazyVariable, LazyVariables};
+///
+/// let variables: Vec<_> = (3..5).map(|value| LazyVariable::new(move || value)).collect();
+/// let tokens = "$0 $0 *".split_whitespace();
+/// let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+/// assert_eq!(expr.evaluate_with_variables(&LazyVariables::new(&variables)), Ok(9));
+/// # }
+/// ```
+///
+/// [`LazyVariable`]: ../variable/struct.LazyVariable.html
+/// [`GetVariable`]: ../variable/trait.GetVariable.html
+#[derive(Debug)]
+pub struct LazyVariables<'a, C: 'a + ?Sized, I, T> {
+    variables: &'a C,
+    #[cfg(feature = "std")]
+    computed: Mutex<BTreeMap<I, T>>,
+    #[cfg(not(feature = "std"))]
+    computed: RefCell<BTreeMap<I, T>>,
+}
+
+impl<'a, C: ?Sized, I: Ord, T> LazyVariables<'a, C, I, T> {
+    /// Creates a container computing the lazy `variables` on demand, with an empty cache.
+    pub fn new(variables: &'a C) -> Self {
+        LazyVariables { variables, computed: Default::default() }
+    }
+
+    #[cfg(feature = "std")]
+    fn with_computed<R, F: FnOnce(&mut BTreeMap<I, T>) -> R>(&self, f: F) -> R {
+        // a panicking variable can't leave the cache in an inconsistent state
+        let mut computed = self.computed.lock().unwrap_or_else(|err| err.into_inner());
+        f(&mut computed)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn with_computed<R, F: FnOnce(&mut BTreeMap<I, T>) -> R>(&self, f: F) -> R {
+        f(&mut self.computed.borrow_mut())
+    }
+}
+
+/// Marker used by the [`GetVariableOwned`] implementation of [`LazyVariables`].
+///
+/// [`GetVariableOwned`]: ../variable/trait.GetVariableOwned.html
+/// [`LazyVariables`]: ../variable/struct.LazyVariables.html
+#[derive(Debug)]
+pub enum ByLazy {}
+
+impl<'a, I, T, F, C> GetVariableOwned<I, ByLazy> for LazyVariables<'a, C, I, T>
+    where I: Ord + Clone,
+          T: Clone,
+          F: Fn() -> T,
+          C: GetVariable<I, Output=LazyVariable<T, F>> + ?Sized
+{
+    type Output = T;
+
+    fn get_variable_owned(&self, index: I) -> Option<Self::Output> {
+        self.with_computed(|computed| {
+            if let Some(value) = computed.get(&index) {
+                return Some(value.clone())
+            }
+            let value = self.variables.get_variable(index.clone())?.compute();
+            computed.insert(index, value.clone());
+            Some(value)
+        })
+    }
+}
+
+#[cfg(all(test, feature = "int-evaluator"))]
+mod tests {
+    use core::cell::Cell;
+    use evaluate::VariableIntExpr;
+    use variable::{IndexVar, LazyVariable, LazyVariables};
+
+    #[test]
+    fn computed_once_per_evaluation() {
+        let calls = [Cell::new(0), Cell::new(0)];
+        let variables: Vec<_> = calls.iter().zip(3..).map(|(calls, value)| {
+            LazyVariable::new(move || {
+                calls.set(calls.get() + 1);
+                value
+            })
+        }).collect();
+
+        let tokens = "$0 $0 + $0 *".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        assert_eq!(expr.evaluate_with_variables(&LazyVariables::new(&variables)), Ok(18));
+        assert_eq!(calls[0].get(), 1);
+        assert_eq!(calls[1].get(), 0);
+
+        assert_eq!(expr.evaluate_with_variables(&LazyVariables::new(&variables)), Ok(18));
+        assert_eq!(calls[0].get(), 2);
+        assert_eq!(calls[1].get(), 0);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn shared_between_threads() {
+        let variables: Vec<Vec<_>> = (0..100).map(|i| {
+            (0..2).map(|j| LazyVariable::new(move || i + j)).collect()
+        }).collect();
+        let lazy: Vec<_> = variables.iter().map(LazyVariables::new).collect();
+
+        let tokens = "$0 $1 *".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        let expected: Vec<_> = (0..100).map(|i| Ok(i * (i + 1))).collect();
+        assert_eq!(expr.par_evaluate_batch(&lazy), expected);
+    }
+}
@@ -2,8 +2,17 @@ mod get_variable;
 mod dummy_variables;
 mod dummy_variable;
 mod index_var;
+mod named_var;
+mod scoped_variables;
+mod lazy_variable;
 
-pub use self::get_variable::GetVariable;
+pub use self::get_variable::{GetVariable, GetVariableMut, GetVariableOwned, GetVariableStr};
+pub use self::get_variable::{ByRef, ByFn};
 pub use self::dummy_variables::DummyVariables;
-pub use self::dummy_variable::DummyVariable;
-pub use self::index_var::IndexVar;
+pub use self::dummy_variable::{DummyVariable, DummyVariableErr};
+pub use self::index_var::{IndexVar, VarIdxErr};
+#[allow(deprecated)]
+pub use self::index_var::VarIdx;
+pub use self::named_var::{NamedVar, NamedVarErr};
+pub use self::scoped_variables::ScopedVariables;
+pub use self::lazy_variable::{LazyVariable, LazyVariables, ByLazy};
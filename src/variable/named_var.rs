@@ -0,0 +1,56 @@
+use core::error::Error;
+use core::fmt;
+use convert_ref::TryFromRef;
+use alloc::string::{String, ToString};
+
+/// Variable keeping a name to retrieve its value from a `String` keyed container,
+/// like an [`HashMap`] for example. Named variables are written `$name`.
+///
+/// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NamedVar(String);
+
+/// Type returned when a token is not a valid named variable.
+#[derive(Debug, PartialEq)]
+pub enum NamedVarErr<'a> {
+    InvalidVariableName(&'a str),
+}
+
+impl<'a> fmt::Display for NamedVarErr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NamedVarErr::InvalidVariableName(name) => write!(f, "invalid variable name: '{}'", name),
+        }
+    }
+}
+
+impl<'a> Error for NamedVarErr<'a> {}
+
+impl<'a> TryFromRef<&'a str> for NamedVar {
+    type Err = NamedVarErr<'a>;
+
+    fn try_from_ref(s: &&'a str) -> Result<Self, Self::Err> {
+        match s.chars().next() {
+            Some('$') if s.len() > 1 => Ok(NamedVar(s[1..].to_string())),
+            _ => Err(NamedVarErr::InvalidVariableName(s)),
+        }
+    }
+}
+
+impl fmt::Display for NamedVar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+impl AsRef<str> for NamedVar {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<NamedVar> for String {
+    fn from(named_var: NamedVar) -> Self {
+        named_var.0
+    }
+}
@@ -0,0 +1,63 @@
+use variable::GetVariable;
+
+/// Variable container chaining two scopes, variables are searched
+/// in the `inner` scope first then in the `outer` one.
+///
+/// Both scopes are borrowed, so any [`GetVariable`] containers
+/// can be composed without allocating.
+///
+/// [`GetVariable`]: ../variable/trait.GetVariable.html
+#[derive(Debug)]
+pub struct ScopedVariables<'a, Inner: 'a + ?Sized, Outer: 'a + ?Sized> {
+    inner: &'a Inner,
+    outer: &'a Outer,
+}
+
+impl<'a, Inner: ?Sized, Outer: ?Sized> ScopedVariables<'a, Inner, Outer> {
+    /// Creates a container looking into `inner` before `outer`.
+    pub fn new(inner: &'a Inner, outer: &'a Outer) -> Self {
+        ScopedVariables { inner, outer }
+    }
+}
+
+impl<'a, Inner: ?Sized, Outer: ?Sized> Clone for ScopedVariables<'a, Inner, Outer> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, Inner: ?Sized, Outer: ?Sized> Copy for ScopedVariables<'a, Inner, Outer> {}
+
+impl<'a, I, T, Inner, Outer> GetVariable<I> for ScopedVariables<'a, Inner, Outer>
+    where I: Clone,
+          Inner: GetVariable<I, Output=T> + ?Sized,
+          Outer: GetVariable<I, Output=T> + ?Sized
+{
+    type Output = T;
+
+    fn get_variable(&self, index: I) -> Option<&Self::Output> {
+        self.inner.get_variable(index.clone()).or_else(|| self.outer.get_variable(index))
+    }
+}
+
+#[cfg(all(test, feature = "int-evaluator"))]
+mod tests {
+    use std::collections::BTreeMap;
+    use evaluate::VariableIntExpr;
+    use variable::{IndexVar, ScopedVariables};
+
+    #[test]
+    fn inner_scope_first() {
+        let mut inner = BTreeMap::new();
+        inner.insert(0, 10);
+        let outer = vec![5, 3];
+
+        let tokens = "$0 $1 +".split_whitespace();
+        let expr = VariableIntExpr::<i32, IndexVar>::from_iter(tokens).unwrap();
+        let scoped = ScopedVariables::new(&inner, &outer);
+        assert_eq!(expr.evaluate_with_variables(&scoped), Ok(13));
+
+        let scoped = ScopedVariables::new(&outer[..], &inner);
+        assert_eq!(expr.evaluate_with_variables(&scoped), Ok(8));
+    }
+}